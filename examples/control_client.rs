@@ -0,0 +1,100 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+// Plays a few games against `flappybird --serve 7777` over the control socket.
+//
+//   cargo run -- --serve 7777
+//   cargo run --example control_client -- 7777
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpStream,
+};
+
+use serde_json::{json, Value};
+
+const GAMES: u32 = 3;
+
+fn send(stream: &mut TcpStream, command: Value) -> std::io::Result<()> {
+    writeln!(stream, "{}", command)
+}
+
+fn main() -> std::io::Result<()> {
+    let port = std::env::args()
+        .nth(1)
+        .and_then(|port| port.parse::<u16>().ok())
+        .unwrap_or(7777);
+    let mut stream = TcpStream::connect(("127.0.0.1", port))?;
+    let reader = BufReader::new(stream.try_clone()?);
+    println!("Connected to localhost:{}", port);
+
+    send(&mut stream, json!({ "command": "reset", "seed": 1 }))?;
+
+    let mut games = 0;
+    let mut over = false;
+    // where to hold the bird until the first pipe shows up
+    let mut cruise = 0.;
+    for line in reader.lines() {
+        let tick: Value = match serde_json::from_str(&line?) {
+            Ok(tick) => tick,
+            Err(err) => {
+                eprintln!("Bad state line: {}", err);
+                continue;
+            }
+        };
+        let state = tick["state"].as_str().unwrap_or_default();
+        match state {
+            "GameOver" if !over => {
+                over = true;
+                games += 1;
+                println!("Game {} scored {}", games, tick["birds"][0]["score"]);
+                if games == GAMES {
+                    break;
+                }
+                send(
+                    &mut stream,
+                    json!({ "command": "reset", "seed": games + 1 }),
+                )?;
+            }
+            "TapTap" => {
+                over = false;
+                cruise = tick["birds"][0]["position"]["y"]
+                    .as_f64()
+                    .unwrap_or_default();
+                send(&mut stream, json!({ "command": "flap" }))?;
+            }
+            "InGame" => {
+                let bird = &tick["birds"][0];
+                let bird_y = bird["position"]["y"].as_f64().unwrap_or_default();
+                let rising = bird["velocity"]["y"].as_f64().unwrap_or_default();
+                let target = tick["gaps"][0]["y"].as_f64().unwrap_or(cruise) - 25.;
+                if bird_y < target && rising < 100. {
+                    send(&mut stream, json!({ "command": "flap" }))?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
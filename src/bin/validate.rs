@@ -0,0 +1,156 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+// Proves seeds beatable without playing them: generates each course the way the game does
+// and searches for a bird that gets through it
+//
+//   validate --seeds 1..1000 --difficulty hard --target 100
+
+use std::ops::Range;
+
+use flappybird::{
+    solver::{solve, Verdict},
+    world::difficulty::DifficultyPreset,
+    Playfield,
+};
+
+struct Options {
+    seeds: Range<u64>,
+    difficulty: DifficultyPreset,
+    // pipes a seed has to be beatable to, a perfect bird could go on for ever
+    target: u64,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            seeds: 0..100,
+            difficulty: DifficultyPreset::Normal,
+            target: 100,
+        }
+    }
+}
+
+// 10..20 or a single seed, an empty range would prove nothing
+fn parse_seeds(value: &str) -> Option<Range<u64>> {
+    let seeds = match value.split_once("..") {
+        Some((from, to)) => from.parse().ok()?..to.parse().ok()?,
+        None => {
+            let seed: u64 = value.parse().ok()?;
+            seed..seed.checked_add(1)?
+        }
+    };
+    (!seeds.is_empty()).then_some(seeds)
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+        let value = inline_value.or_else(|| args.next());
+        match flag.as_str() {
+            "--seeds" => {
+                options.seeds = value
+                    .as_deref()
+                    .and_then(parse_seeds)
+                    .ok_or("--seeds expects a seed or a non-empty range like 10..20")?
+            }
+            "--difficulty" => {
+                options.difficulty = value
+                    .as_deref()
+                    .and_then(DifficultyPreset::parse)
+                    .ok_or("--difficulty expects easy, normal or hard")?
+            }
+            "--target" => {
+                options.target = value
+                    .as_deref()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or("--target expects a number")?
+            }
+            _ => return Err(format!("Unknown argument {}", flag)),
+        }
+    }
+    Ok(options)
+}
+
+fn main() {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}", error);
+            eprintln!("Usage: validate [--seeds 1..1000] [--difficulty hard] [--target 100]");
+            std::process::exit(2);
+        }
+    };
+    println!(
+        "Validating seeds {}..{} on {} up to {} pipes",
+        options.seeds.start,
+        options.seeds.end,
+        options.difficulty.label(),
+        options.target
+    );
+
+    let mut unbeatable: Vec<Verdict> = Vec::new();
+    let mut total = 0;
+    for seed in options.seeds.clone() {
+        let verdict = solve(
+            seed,
+            options.difficulty,
+            Playfield::default(),
+            options.target,
+        );
+        total += 1;
+        if verdict.beaten {
+            println!(
+                "seed {}: beatable, best bird reaches {} after {} ticks",
+                seed, verdict.score, verdict.ticks
+            );
+        } else {
+            println!(
+                "seed {}: best bird crashes at {} after {} ticks",
+                seed, verdict.score, verdict.ticks
+            );
+            unbeatable.push(verdict);
+        }
+    }
+
+    println!(
+        "{} of {} seeds beatable to {} pipes",
+        total - unbeatable.len(),
+        total,
+        options.target
+    );
+    if !unbeatable.is_empty() {
+        let seeds: Vec<String> = unbeatable
+            .iter()
+            .map(|verdict| verdict.seed.to_string())
+            .collect();
+        println!("Unbeatable: {}", seeds.join(" "));
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,109 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::world::difficulty::DifficultyPreset;
+
+// Command line options, values go after the flag or after an `=`
+#[derive(Resource, Debug, Default, Clone)]
+pub struct Args {
+    // --seed 1234
+    pub seed: Option<u64>,
+    // --replay path/to/run.fbr
+    pub replay: Option<PathBuf>,
+    // --headless, simulate without a window, renderer or audio
+    pub headless: bool,
+    // --runs 100, how many games a headless session plays
+    pub runs: Option<u32>,
+    // --autopilot, let the built-in bot fly every run
+    pub autopilot: bool,
+    // --serve 7777, accept bots on this localhost port
+    pub serve: Option<u16>,
+    // --genome path/to/genome.ron, the autopilot flies with a bred network
+    pub genome: Option<PathBuf>,
+    // --difficulty hard, overrides the preset from the settings
+    pub difficulty: Option<DifficultyPreset>,
+}
+
+impl Args {
+    pub fn parse() -> Self {
+        Self::parse_from(std::env::args().skip(1))
+    }
+
+    pub fn parse_from(args: impl IntoIterator<Item = String>) -> Self {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            match flag.as_str() {
+                "--seed" => {
+                    let value = inline_value.or_else(|| args.next());
+                    match value.as_deref().map(str::parse::<u64>) {
+                        Some(Ok(seed)) => parsed.seed = Some(seed),
+                        _ => eprintln!("--seed expects a number"),
+                    }
+                }
+                "--genome" => match inline_value.or_else(|| args.next()) {
+                    Some(path) => parsed.genome = Some(PathBuf::from(path)),
+                    None => eprintln!("--genome expects a file"),
+                },
+                "--difficulty" => {
+                    let value = inline_value.or_else(|| args.next());
+                    match value.as_deref().map(DifficultyPreset::parse) {
+                        Some(Some(preset)) => parsed.difficulty = Some(preset),
+                        _ => eprintln!("--difficulty expects easy, normal or hard"),
+                    }
+                }
+                "--replay" => match inline_value.or_else(|| args.next()) {
+                    Some(path) => parsed.replay = Some(PathBuf::from(path)),
+                    None => eprintln!("--replay expects a file"),
+                },
+                "--headless" => parsed.headless = true,
+                "--autopilot" => parsed.autopilot = true,
+                "--serve" => {
+                    let value = inline_value.or_else(|| args.next());
+                    match value.as_deref().map(str::parse::<u16>) {
+                        Some(Ok(port)) => parsed.serve = Some(port),
+                        _ => eprintln!("--serve expects a port"),
+                    }
+                }
+                "--runs" => {
+                    let value = inline_value.or_else(|| args.next());
+                    match value.as_deref().map(str::parse::<u32>) {
+                        Some(Ok(runs)) => parsed.runs = Some(runs),
+                        _ => eprintln!("--runs expects a number"),
+                    }
+                }
+                _ => eprintln!("Ignoring unknown argument {}", flag),
+            }
+        }
+        parsed
+    }
+}
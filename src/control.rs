@@ -0,0 +1,398 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+// Lets bots in any language drive a running game over a localhost socket.
+// The game writes one JSON object per fixed tick, one per line, with an entry
+// for every bird in `birds`, and reads commands back in the same format:
+//
+//   {"command":"flap"}
+//   {"command":"reset","seed":1234}
+//   {"command":"pause","paused":true}
+//
+// Pausing works like Escape, a run in game pauses and resumes after the countdown
+
+use std::{
+    io::{ErrorKind, Read, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+};
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::Args,
+    modes::GameMode,
+    player::{
+        autopilot::gaps_ahead,
+        controller::{FlapIntent, InputSource},
+        Eliminated, Player, Seat,
+    },
+    replay::RunTick,
+    timestep::GameplaySet,
+    ui::{pause::Resume, score::ScoreUI, taptap::spawn_taptap_screen, titlescreen::start_run},
+    world::{pipes::Pipe, WorldRng},
+    FlappybirdState, Playfield,
+};
+
+// A client that can't keep up with the state stream gets dropped past this many bytes
+const MAX_OUTBOX: usize = 1 << 20;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum Command {
+    Flap,
+    Reset { seed: Option<u64> },
+    // toggles when `paused` is left out
+    Pause { paused: Option<bool> },
+}
+
+#[derive(Debug, Serialize)]
+struct Point {
+    x: f32,
+    y: f32,
+}
+
+impl From<Vec2> for Point {
+    fn from(value: Vec2) -> Self {
+        Self {
+            x: value.x,
+            y: value.y,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct BirdState {
+    score: u64,
+    position: Point,
+    velocity: Point,
+    // a crashed bird keeps falling until the run is over
+    alive: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct TickState {
+    tick: u32,
+    state: String,
+    seed: u64,
+    // player one first, then player two, the evolve population in no order
+    birds: Vec<BirdState>,
+    // gap centers still ahead of the birds, nearest first
+    gaps: Vec<Point>,
+}
+
+struct Client {
+    stream: TcpStream,
+    inbox: Vec<u8>,
+    outbox: Vec<u8>,
+}
+
+impl Client {
+    // Reads whatever arrived without blocking, false once the client is gone
+    fn receive(&mut self) -> bool {
+        let mut buffer = [0; 1024];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => return false,
+                Ok(read) => self.inbox.extend_from_slice(&buffer[..read]),
+                Err(err) if err.kind() == ErrorKind::WouldBlock => return true,
+                Err(_) => return false,
+            }
+        }
+    }
+
+    fn next_line(&mut self) -> Option<String> {
+        let end = self.inbox.iter().position(|&byte| byte == b'\n')?;
+        let line: Vec<u8> = self.inbox.drain(..=end).collect();
+        Some(String::from_utf8_lossy(&line).trim().to_string())
+    }
+
+    // Writes as much of the outbox as the socket takes, false once the client is gone
+    fn flush(&mut self) -> bool {
+        while !self.outbox.is_empty() {
+            match self.stream.write(&self.outbox) {
+                Ok(0) => return false,
+                Ok(written) => {
+                    self.outbox.drain(..written);
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => return false,
+            }
+        }
+        self.outbox.len() <= MAX_OUTBOX
+    }
+}
+
+#[derive(Resource)]
+struct ControlServer {
+    listener: TcpListener,
+    clients: Vec<Client>,
+}
+
+#[derive(Resource, Default)]
+struct PendingReset(Option<Option<u64>>);
+
+fn start_server(port: u16) -> std::io::Result<ControlServer> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+    listener.set_nonblocking(true)?;
+    Ok(ControlServer {
+        listener,
+        clients: Vec::new(),
+    })
+}
+
+// While a bot is connected it owns the bird, runs it flies are not the player's
+fn accept_clients(mut server: ResMut<ControlServer>, mut input_source: ResMut<InputSource>) {
+    loop {
+        match server.listener.accept() {
+            Ok((stream, address)) => {
+                if let Err(err) = stream.set_nonblocking(true) {
+                    eprintln!("Dropping control client {}: {}", address, err);
+                    continue;
+                }
+                let _ = stream.set_nodelay(true);
+                println!("Control client connected from {}", address);
+                server.clients.push(Client {
+                    stream,
+                    inbox: Vec::new(),
+                    outbox: Vec::new(),
+                });
+            }
+            Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+            Err(err) => {
+                eprintln!("Control server failed to accept: {}", err);
+                break;
+            }
+        }
+    }
+
+    if server.clients.is_empty() {
+        if *input_source == InputSource::Agent {
+            *input_source = InputSource::Human;
+        }
+    } else if *input_source == InputSource::Human {
+        *input_source = InputSource::Agent;
+    }
+}
+
+fn read_commands(
+    mut server: ResMut<ControlServer>,
+    mut pending_reset: ResMut<PendingReset>,
+    state: Res<State<FlappybirdState>>,
+    mut next_state: ResMut<NextState<FlappybirdState>>,
+    mut resume: EventWriter<Resume>,
+    mut player_query: Query<&mut FlapIntent, With<Player>>,
+) {
+    let mut commands = Vec::new();
+    server.clients.retain_mut(|client| {
+        let connected = client.receive();
+        while let Some(line) = client.next_line() {
+            if line.is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<Command>(&line) {
+                Ok(command) => commands.push(command),
+                Err(err) => eprintln!("Ignoring control command {:?}: {}", line, err),
+            }
+        }
+        if !connected {
+            println!("Control client disconnected");
+        }
+        connected
+    });
+
+    for command in commands {
+        match command {
+            Command::Flap => {
+                for mut intent in player_query.iter_mut() {
+                    intent.0 = true;
+                }
+            }
+            Command::Reset { seed } => pending_reset.0 = Some(seed),
+            Command::Pause { paused } => {
+                let is_paused = state.get() == &FlappybirdState::Paused;
+                match (paused.unwrap_or(!is_paused), state.get()) {
+                    (true, FlappybirdState::InGame) => next_state.set(FlappybirdState::Paused),
+                    (false, FlappybirdState::Paused) => {
+                        resume.send(Resume);
+                    }
+                    (true, FlappybirdState::Paused) | (false, FlappybirdState::InGame) => {}
+                    (_, state) => eprintln!("Ignoring control pause while {:?}", state),
+                }
+            }
+        }
+    }
+}
+
+// Starts a fresh run from wherever the game is, the same way the buttons would
+fn apply_reset(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut pending_reset: ResMut<PendingReset>,
+    mut args: ResMut<Args>,
+    mut mode: ResMut<GameMode>,
+    state: Res<State<FlappybirdState>>,
+    mut next_state: ResMut<NextState<FlappybirdState>>,
+    mut player_query: Query<(&mut Transform, &Seat), With<Player>>,
+    mut score_query: Query<&mut Text, With<ScoreUI>>,
+    playfield: Res<Playfield>,
+) {
+    // the falling bird has to land before the course can be cleared
+    if state.get() == &FlappybirdState::Dying {
+        return;
+    }
+    let Some(seed) = pending_reset.0.take() else {
+        return;
+    };
+    if seed.is_some() {
+        args.seed = seed;
+    }
+    match state.get() {
+        FlappybirdState::TapTap => {
+            if let Some(seed) = seed {
+                commands.insert_resource(WorldRng::new(seed));
+            }
+        }
+        FlappybirdState::InGame | FlappybirdState::Paused => {
+            for mut text in score_query.iter_mut() {
+                text.sections[0].value = "0".to_string();
+            }
+            spawn_taptap_screen(&mut commands, &asset_server);
+            next_state.set(FlappybirdState::TapTap);
+        }
+        FlappybirdState::Dying | FlappybirdState::GameOver => {
+            spawn_taptap_screen(&mut commands, &asset_server);
+            next_state.set(FlappybirdState::TapTap);
+        }
+        FlappybirdState::MainTitle
+        | FlappybirdState::Settings
+        | FlappybirdState::Scoreboard
+        | FlappybirdState::LevelSelect
+        | FlappybirdState::Evolve => {
+            // the evolve population is still about, the run is player one's
+            let Some((mut transform, _)) =
+                player_query.iter_mut().find(|(_, seat)| **seat == Seat(0))
+            else {
+                eprintln!("Ignoring control reset, there is no bird to fly");
+                return;
+            };
+            *mode = GameMode::Endless;
+            start_run(
+                &mut commands,
+                &asset_server,
+                &mut next_state,
+                &mut transform,
+                &playfield,
+            );
+        }
+    }
+}
+
+fn stream_state(
+    mut server: ResMut<ControlServer>,
+    state: Res<State<FlappybirdState>>,
+    tick: Res<RunTick>,
+    rng: Res<WorldRng>,
+    player_query: Query<(
+        &Player,
+        &Transform,
+        &Velocity,
+        Option<&Seat>,
+        Has<Eliminated>,
+    )>,
+    pipe_query: Query<&Transform, With<Pipe>>,
+) {
+    if server.clients.is_empty() {
+        return;
+    }
+    let mut birds: Vec<_> = player_query.iter().collect();
+    birds.sort_by_key(|(_, _, _, seat, _)| seat.map_or(usize::MAX, |seat| seat.0));
+    // every bird flies at the same x, any of them sees the same gaps ahead
+    let gaps = birds.first().map_or(Vec::new(), |(_, transform, ..)| {
+        gaps_ahead(transform.translation.truncate(), pipe_query.iter())
+            .into_iter()
+            .map(Point::from)
+            .collect()
+    });
+    let tick_state = TickState {
+        tick: tick.0,
+        state: format!("{:?}", state.get()),
+        seed: rng.seed,
+        birds: birds
+            .into_iter()
+            .map(|(player, transform, velocity, _, eliminated)| BirdState {
+                score: player.score,
+                position: transform.translation.truncate().into(),
+                velocity: velocity.linvel.into(),
+                alive: !eliminated,
+            })
+            .collect(),
+        gaps,
+    };
+    let mut line = match serde_json::to_vec(&tick_state) {
+        Ok(line) => line,
+        Err(err) => {
+            eprintln!("Failed to encode game state: {}", err);
+            return;
+        }
+    };
+    line.push(b'\n');
+
+    server.clients.retain_mut(|client| {
+        client.outbox.extend_from_slice(&line);
+        let connected = client.flush();
+        if !connected {
+            println!("Control client dropped");
+        }
+        connected
+    });
+}
+
+pub struct ControlPlugin {
+    pub port: u16,
+}
+
+impl Plugin for ControlPlugin {
+    fn build(&self, app: &mut App) {
+        let server = match start_server(self.port) {
+            Ok(server) => server,
+            Err(err) => {
+                eprintln!(
+                    "Failed to start control server on port {}: {}",
+                    self.port, err
+                );
+                return;
+            }
+        };
+        println!("Control server listening on localhost:{}", self.port);
+        app.insert_resource(server)
+            .init_resource::<PendingReset>()
+            .add_systems(
+                PreUpdate,
+                (accept_clients, read_commands, apply_reset).chain(),
+            )
+            .add_systems(FixedUpdate, stream_state.after(GameplaySet::Collisions));
+    }
+}
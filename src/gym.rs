@@ -0,0 +1,198 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+// A gym style environment over the headless simulation, for training agents
+// without a window. One step is one fixed tick of the game.
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    cli::Args,
+    headless::headless_app,
+    player::{
+        autopilot::gaps_ahead,
+        controller::{FlapIntent, InputSource},
+        Player,
+    },
+    world::{pipes::Pipe, WorldSpeed},
+    FlappybirdState,
+};
+
+pub const ALIVE_REWARD: f32 = 0.1;
+pub const PIPE_REWARD: f32 = 1.0;
+pub const DEATH_REWARD: f32 = -1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Idle,
+    Flap,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PipeGap {
+    // horizontal distance from the bird to the gap center
+    pub distance: f32,
+    pub height: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Observation {
+    pub bird_height: f32,
+    pub vertical_velocity: f32,
+    pub world_speed: f32,
+    // the next two gaps ahead of the bird, nearest first
+    pub gaps: [Option<PipeGap>; 2],
+}
+
+pub struct FlappyEnv {
+    app: App,
+    score: u64,
+    done: bool,
+}
+
+impl Default for FlappyEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FlappyEnv {
+    pub fn new() -> Self {
+        let mut app = headless_app(Args::default());
+        app.insert_resource(InputSource::Agent);
+        // runs the startup systems that spawn the bird and the ground
+        app.update();
+        Self {
+            app,
+            score: 0,
+            done: true,
+        }
+    }
+
+    // Starts a fresh episode on the course for `seed`. Every run begins with a flap,
+    // so the bird is already in the air when the first observation comes back
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.app.world_mut().resource_mut::<Args>().seed = Some(seed);
+        // going through game over is what puts the bird and pipes back
+        self.set_state(FlappybirdState::GameOver);
+        self.set_state(FlappybirdState::TapTap);
+        self.flap();
+        self.app.update();
+
+        self.score = 0;
+        self.done = false;
+        self.observe()
+    }
+
+    pub fn step(&mut self, action: Action) -> (Observation, f32, bool) {
+        if self.done {
+            return (self.observe(), 0., true);
+        }
+        if action == Action::Flap {
+            self.flap();
+        }
+        self.app.update();
+
+        let score = self.current_score();
+        let mut reward = ALIVE_REWARD + (score - self.score) as f32 * PIPE_REWARD;
+        self.score = score;
+        if self.is_over() {
+            self.done = true;
+            reward += DEATH_REWARD;
+        }
+        (self.observe(), reward, self.done)
+    }
+
+    pub fn score(&self) -> u64 {
+        self.score
+    }
+
+    pub fn observe(&mut self) -> Observation {
+        let world = self.app.world_mut();
+        let world_speed = world.resource::<WorldSpeed>().0;
+        let Ok((transform, velocity)) = world
+            .query_filtered::<(&Transform, &Velocity), With<Player>>()
+            .get_single(world)
+        else {
+            return Observation::default();
+        };
+        let bird = transform.translation.truncate();
+        let vertical_velocity = velocity.linvel.y;
+
+        let mut pipe_query = world.query_filtered::<&Transform, With<Pipe>>();
+        let ahead = gaps_ahead(bird, pipe_query.iter(world));
+        let mut gaps = [None; 2];
+        for (slot, gap) in gaps.iter_mut().zip(ahead) {
+            *slot = Some(PipeGap {
+                distance: gap.x - bird.x,
+                height: gap.y,
+            });
+        }
+
+        Observation {
+            bird_height: bird.y,
+            vertical_velocity,
+            world_speed,
+            gaps,
+        }
+    }
+
+    fn set_state(&mut self, state: FlappybirdState) {
+        self.app
+            .world_mut()
+            .resource_mut::<NextState<FlappybirdState>>()
+            .set(state);
+        self.app.update();
+    }
+
+    fn flap(&mut self) {
+        let world = self.app.world_mut();
+        let mut intents = world.query_filtered::<&mut FlapIntent, With<Player>>();
+        for mut intent in intents.iter_mut(world) {
+            intent.0 = true;
+        }
+    }
+
+    fn current_score(&mut self) -> u64 {
+        let world = self.app.world_mut();
+        world
+            .query::<&Player>()
+            .get_single(world)
+            .map_or(0, |player| player.score)
+    }
+
+    // Death only takes effect on the next state transition, so a pending one counts too,
+    // the episode ends on the crash rather than when the bird lands
+    fn is_over(&self) -> bool {
+        let world = self.app.world();
+        matches!(
+            world.resource::<State<FlappybirdState>>().get(),
+            FlappybirdState::Dying | FlappybirdState::GameOver
+        ) || matches!(
+            world.resource::<NextState<FlappybirdState>>(),
+            NextState::Pending(FlappybirdState::Dying | FlappybirdState::GameOver)
+        )
+    }
+}
@@ -0,0 +1,163 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::time::Duration;
+
+use bevy::{app::PluginsState, prelude::*, state::app::StatesPlugin, time::TimeUpdateStrategy};
+
+use crate::{
+    cli::Args,
+    modes::GameMode,
+    player::{controller::InputSource, Player},
+    replay::{ReplayPlayback, RunTick},
+    timestep::TICK_RATE,
+    ui::{settings::Settings, titlescreen::start_run},
+    world::WorldRng,
+    FlappybirdState, Playfield, SimulationPlugin,
+};
+
+// How a single simulated game ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunResult {
+    pub seed: u64,
+    pub score: u64,
+    pub ticks: u32,
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct HeadlessRuns {
+    pub total: u32,
+    pub results: Vec<RunResult>,
+}
+
+// Starts the next game from the title, or stops once every run is done or nothing can fly the bird
+fn drive_runs(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    runs: Res<HeadlessRuns>,
+    input_source: Res<InputSource>,
+    mut mode: ResMut<GameMode>,
+    mut state: ResMut<NextState<FlappybirdState>>,
+    mut player_query: Query<&mut Transform, With<Player>>,
+    playfield: Res<Playfield>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if runs.results.len() as u32 >= runs.total || *input_source == InputSource::Human {
+        exit.send(AppExit::Success);
+        return;
+    }
+    let Ok(mut transform) = player_query.get_single_mut() else {
+        return;
+    };
+    *mode = GameMode::Endless;
+    start_run(
+        &mut commands,
+        &asset_server,
+        &mut state,
+        &mut transform,
+        &playfield,
+    );
+}
+
+fn collect_result(
+    mut runs: ResMut<HeadlessRuns>,
+    rng: Res<WorldRng>,
+    tick: Res<RunTick>,
+    player_query: Query<&Player>,
+    mut state: ResMut<NextState<FlappybirdState>>,
+) {
+    let result = RunResult {
+        seed: rng.seed,
+        score: player_query.get_single().map_or(0, |player| player.score),
+        ticks: tick.0,
+    };
+    println!(
+        "Run {}: seed {} scored {} in {} ticks",
+        runs.results.len() + 1,
+        result.seed,
+        result.score,
+        result.ticks
+    );
+    runs.results.push(result);
+    state.set(FlappybirdState::MainTitle);
+}
+
+// A finished app with the whole simulation but no window, renderer or audio device.
+// Every update advances exactly one fixed tick, as fast as the CPU allows
+pub fn headless_app(args: Args) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        HierarchyPlugin,
+        StatesPlugin,
+        AssetPlugin::default(),
+    ))
+    // sprites still get handles, nothing ever loads or draws them
+    .init_asset::<Image>()
+    .init_asset::<TextureAtlasLayout>()
+    .init_asset::<Font>()
+    .init_asset::<Mesh>()
+    .add_plugins(SimulationPlugin)
+    .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        1. / TICK_RATE,
+    )))
+    .insert_resource(Settings::default())
+    .insert_resource(args);
+
+    while app.plugins_state() == PluginsState::Adding {
+        #[cfg(not(target_arch = "wasm32"))]
+        bevy::tasks::tick_global_task_pools_on_main_thread();
+    }
+    app.finish();
+    app.cleanup();
+    app
+}
+
+// Plays whole games back to back, flown by the replay or the autopilot
+pub fn run_headless(args: Args) -> Vec<RunResult> {
+    if args.replay.is_none() && !args.autopilot {
+        eprintln!("Nothing is flying the bird, pass --autopilot or --replay");
+    }
+    let total = args.runs.unwrap_or(1);
+
+    let mut app = headless_app(args);
+    app.insert_resource(HeadlessRuns {
+        total,
+        results: Vec::new(),
+    })
+    .add_systems(
+        Update,
+        drive_runs.run_if(
+            in_state(FlappybirdState::MainTitle).and_then(not(resource_exists::<ReplayPlayback>)),
+        ),
+    )
+    .add_systems(OnEnter(FlappybirdState::GameOver), collect_result);
+
+    while app.should_exit().is_none() {
+        app.update();
+    }
+
+    std::mem::take(&mut app.world_mut().resource_mut::<HeadlessRuns>().results)
+}
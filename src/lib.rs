@@ -0,0 +1,207 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_embedded_assets::EmbeddedAssetPlugin;
+use bevy_inspector_egui::quick::WorldInspectorPlugin;
+use bevy_kira_audio::AudioPlugin;
+use bevy_rapier2d::plugin::{NoUserData, RapierPhysicsPlugin};
+use bevy_rapier2d::prelude::*;
+use cli::Args;
+use control::ControlPlugin;
+use modes::{
+    daily::DailyPlugin, demo::DemoPlugin, evolve::EvolvePlugin, levels::LevelsPlugin,
+    versus::VersusPlugin, GameMode,
+};
+use mods::ModsPlugin;
+use player::{
+    autopilot::AutopilotPlugin,
+    death::{DeathEffectsPlugin, DeathPlugin},
+    ghost::GhostPlugin,
+    PlayerInputPlugin, PlayerPlugin,
+};
+use replay::ReplayPlugin;
+use sfx::{PlaySfx, SfxPlugin};
+use timestep::TimestepPlugin;
+use ui::gameover::GameOverPlugin;
+use ui::leaderboard::LeaderboardPlugin;
+use ui::medal::MedalPlugin;
+use ui::pause::PausePlugin;
+use ui::settings::SettingsPlugin;
+use ui::titlescreen::TitlescreenPlugin;
+use world::difficulty::DifficultyPlugin;
+use world::ground::GroundPlugin;
+use world::pipes::PipePlugin;
+use world::sky::SkyPlugin;
+use world::WorldSpeed;
+
+pub mod cli;
+pub mod control;
+pub mod gym;
+pub mod headless;
+pub mod modes;
+pub mod mods;
+pub mod player;
+pub mod replay;
+pub mod sfx;
+pub mod solver;
+pub mod storage;
+pub mod timestep;
+pub mod ui;
+pub mod world;
+
+pub const PIXELS_PER_METER: f32 = 100.0;
+
+#[derive(States, Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum FlappybirdState {
+    #[default]
+    MainTitle,
+    TapTap,
+    InGame,
+    // the crashed bird falls to the ground before the game over screen
+    Dying,
+    GameOver,
+    Settings,
+    Scoreboard,
+    Evolve,
+    Paused,
+    LevelSelect,
+}
+
+// The area the game is played in, follows the window when there is one
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct Playfield {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for Playfield {
+    fn default() -> Self {
+        Self {
+            width: 1280.,
+            height: 720.,
+        }
+    }
+}
+
+// Everything a game needs to play out, with or without a window
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(PIXELS_PER_METER)
+                .in_fixed_schedule(),
+            TimestepPlugin,
+            PlayerPlugin,
+            AutopilotPlugin,
+            DeathPlugin,
+            PipePlugin,
+            DifficultyPlugin,
+            GroundPlugin,
+            ReplayPlugin,
+        ))
+        .init_state::<FlappybirdState>()
+        .init_resource::<GameMode>()
+        .init_resource::<Playfield>()
+        .insert_resource(WorldSpeed(150.0))
+        .add_event::<PlaySfx>();
+    }
+}
+
+pub fn run(args: Args) {
+    // check to see if 1 week elapsed from today
+
+    let mut app = App::new();
+    app.add_plugins((
+        // the mods asset source has to exist before the AssetPlugin is built
+        (ModsPlugin, EmbeddedAssetPlugin::default()),
+        DefaultPlugins
+            .set(ImagePlugin::default_nearest())
+            .set(WindowPlugin {
+                primary_window: Some(Window {
+                    title: "Flappy Bird".to_string(),
+                    name: Some("Flappy Bird".to_string()),
+                    //resolution: (1179. / 4., 2556. / 4.).into(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+        SimulationPlugin,
+        // off until the settings turn it on, release builds included
+        RapierDebugRenderPlugin {
+            enabled: false,
+            ..default()
+        },
+        //#[cfg(debug_assertions)]
+        //WorldInspectorPlugin::new(),
+        AudioPlugin,
+        SfxPlugin,
+        PlayerInputPlugin,
+        TitlescreenPlugin,
+        GameOverPlugin,
+        MedalPlugin,
+        LeaderboardPlugin,
+        SettingsPlugin,
+        SkyPlugin,
+        DailyPlugin,
+        DemoPlugin,
+    ))
+    .add_plugins((
+        EvolvePlugin,
+        GhostPlugin,
+        VersusPlugin,
+        LevelsPlugin,
+        PausePlugin,
+        DeathEffectsPlugin,
+    ))
+    .add_systems(PreStartup, fit_playfield_to_window)
+    .add_systems(First, fit_playfield_to_window)
+    .add_systems(Startup, spawn_camera);
+
+    if let Some(port) = args.serve {
+        app.add_plugins(ControlPlugin { port });
+    }
+
+    app.insert_resource(args).run();
+}
+
+fn fit_playfield_to_window(
+    window_query: Query<&Window, (With<PrimaryWindow>, Changed<Window>)>,
+    mut playfield: ResMut<Playfield>,
+) {
+    if let Ok(window) = window_query.get_single() {
+        playfield.set_if_neq(Playfield {
+            width: window.width(),
+            height: window.height(),
+        });
+    }
+}
+
+pub fn spawn_camera(mut commands: Commands, playfield: Res<Playfield>) {
+    commands.spawn(Camera2dBundle {
+        transform: Transform::from_xyz(playfield.width / 2., playfield.height / 2., 0.),
+        ..Default::default()
+    });
+}
@@ -0,0 +1,149 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::{player::Player, storage, ui::settings::Settings, FlappybirdState};
+
+use super::GameMode;
+
+const DAILY_FILE: &str = "daily.ron";
+const DAILY_VERSION: u32 = 1;
+
+pub fn today() -> NaiveDate {
+    chrono::Local::now().date_naive()
+}
+
+// The seed reads as the date, 2024-08-03 plays seed 20240803
+pub fn daily_seed(date: NaiveDate) -> u64 {
+    date.year() as u64 * 10000 + date.month() as u64 * 100 + date.day() as u64
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DailyRecord {
+    pub attempts: u32,
+    pub best: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DailyFile {
+    version: u32,
+    days: BTreeMap<String, DailyRecord>,
+}
+
+// Per-day attempts and best scores keyed by ISO date, kept apart from the endless leaderboard
+#[derive(Resource, Debug, Default)]
+pub struct DailyRecords {
+    pub days: BTreeMap<String, DailyRecord>,
+}
+
+impl DailyRecords {
+    pub fn load() -> Self {
+        match storage::load::<DailyFile>(DAILY_FILE) {
+            Some(file) if file.version == DAILY_VERSION => Self { days: file.days },
+            Some(file) => {
+                eprintln!(
+                    "Ignoring daily records with unknown version {}",
+                    file.version
+                );
+                Self::default()
+            }
+            None => Self::default(),
+        }
+    }
+
+    pub fn save(&self) {
+        storage::save(
+            DAILY_FILE,
+            &DailyFile {
+                version: DAILY_VERSION,
+                days: self.days.clone(),
+            },
+        );
+    }
+
+    pub fn record(&self, date: NaiveDate) -> DailyRecord {
+        self.days
+            .get(&date.to_string())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn record_mut(&mut self, date: NaiveDate) -> &mut DailyRecord {
+        self.days.entry(date.to_string()).or_default()
+    }
+
+    pub fn attempts_left(&self, date: NaiveDate, settings: &Settings) -> u32 {
+        settings
+            .daily_attempts
+            .saturating_sub(self.record(date).attempts)
+    }
+}
+
+fn count_daily_attempt(mode: Res<GameMode>, mut records: ResMut<DailyRecords>) {
+    if *mode != GameMode::Daily {
+        return;
+    }
+    records.record_mut(today()).attempts += 1;
+    records.save();
+}
+
+pub fn record_daily_score(
+    mode: Res<GameMode>,
+    player_query: Query<&Player>,
+    mut records: ResMut<DailyRecords>,
+) {
+    if *mode != GameMode::Daily {
+        return;
+    }
+    let Ok(player) = player_query.get_single() else {
+        return;
+    };
+    let record = records.record_mut(today());
+    if player.score > record.best {
+        record.best = player.score;
+        records.save();
+    }
+}
+
+pub struct DailyPlugin;
+
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DailyRecords::load())
+            // resuming from a pause enters InGame again, only the first flap counts
+            .add_systems(
+                OnTransition {
+                    exited: FlappybirdState::TapTap,
+                    entered: FlappybirdState::InGame,
+                },
+                count_daily_attempt,
+            )
+            .add_systems(OnEnter(FlappybirdState::GameOver), record_daily_score);
+    }
+}
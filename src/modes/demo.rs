@@ -0,0 +1,188 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use bevy::prelude::*;
+
+use crate::{
+    cli::Args,
+    player::{controller::InputSource, reset_player, Player},
+    ui::{
+        taptap::TapTapUI,
+        titlescreen::{reset_title_scene, start_run},
+    },
+    world::pipes::clear_pipes,
+    FlappybirdState, Playfield,
+};
+
+use super::GameMode;
+
+// Seconds the title screen sits untouched before the bot starts showing off
+const ATTRACT_DELAY: f32 = 10.;
+// Seconds a finished demo lingers before going back to the title
+const DEMO_OUTRO: f32 = 3.;
+
+// Present while the autopilot is playing a demo run from the title screen
+#[derive(Resource)]
+pub struct DemoRun {
+    outro: Timer,
+}
+
+#[derive(Resource)]
+struct IdleTimer(Timer);
+
+fn any_input(
+    keyboard_input: &ButtonInput<KeyCode>,
+    mouse_input: &ButtonInput<MouseButton>,
+) -> bool {
+    keyboard_input.get_just_pressed().next().is_some()
+        || mouse_input.get_just_pressed().next().is_some()
+}
+
+pub fn start_demo(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    state: &mut NextState<FlappybirdState>,
+    input_source: &mut InputSource,
+    mode: &mut GameMode,
+    transform: &mut Transform,
+    playfield: &Playfield,
+) {
+    println!("Starting demo run");
+    commands.insert_resource(DemoRun {
+        outro: Timer::from_seconds(DEMO_OUTRO, TimerMode::Once),
+    });
+    *input_source = InputSource::Autopilot;
+    *mode = GameMode::Endless;
+    start_run(commands, asset_server, state, transform, playfield);
+}
+
+fn reset_idle_timer(mut idle: ResMut<IdleTimer>) {
+    idle.0.reset();
+}
+
+fn attract_when_idle(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut idle: ResMut<IdleTimer>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    asset_server: Res<AssetServer>,
+    mut state: ResMut<NextState<FlappybirdState>>,
+    mut input_source: ResMut<InputSource>,
+    mut mode: ResMut<GameMode>,
+    mut player_query: Query<&mut Transform, With<Player>>,
+    playfield: Res<Playfield>,
+) {
+    if any_input(&keyboard_input, &mouse_input) {
+        idle.0.reset();
+        return;
+    }
+    if !idle.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    let Ok(mut transform) = player_query.get_single_mut() else {
+        return;
+    };
+    start_demo(
+        &mut commands,
+        &asset_server,
+        &mut state,
+        &mut input_source,
+        &mut mode,
+        &mut transform,
+        &playfield,
+    );
+}
+
+// Any key or click hands the game back to the title screen
+fn leave_demo_on_input(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    taptap_query: Query<Entity, With<TapTapUI>>,
+    mut state: ResMut<NextState<FlappybirdState>>,
+) {
+    if !any_input(&keyboard_input, &mouse_input) {
+        return;
+    }
+    for entity in taptap_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    state.set(FlappybirdState::MainTitle);
+}
+
+fn finish_demo(
+    time: Res<Time>,
+    mut demo: ResMut<DemoRun>,
+    mut state: ResMut<NextState<FlappybirdState>>,
+) {
+    if demo.outro.tick(time.delta()).just_finished() {
+        state.set(FlappybirdState::MainTitle);
+    }
+}
+
+fn end_demo(mut commands: Commands, args: Res<Args>, mut input_source: ResMut<InputSource>) {
+    commands.remove_resource::<DemoRun>();
+    *input_source = if args.autopilot {
+        InputSource::Autopilot
+    } else {
+        InputSource::Human
+    };
+}
+
+pub struct DemoPlugin;
+
+impl Plugin for DemoPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(IdleTimer(Timer::from_seconds(
+            ATTRACT_DELAY,
+            TimerMode::Once,
+        )))
+        .add_systems(OnEnter(FlappybirdState::MainTitle), reset_idle_timer)
+        // a demo can be cut short mid-flight, so the course is cleared here too
+        .add_systems(
+            OnEnter(FlappybirdState::MainTitle),
+            (reset_player, clear_pipes, end_demo)
+                .before(reset_title_scene)
+                .run_if(resource_exists::<DemoRun>),
+        )
+        .add_systems(
+            Update,
+            attract_when_idle.run_if(
+                in_state(FlappybirdState::MainTitle).and_then(not(resource_exists::<DemoRun>)),
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                leave_demo_on_input,
+                finish_demo.run_if(in_state(FlappybirdState::GameOver)),
+            )
+                .chain()
+                .run_if(
+                    resource_exists::<DemoRun>.and_then(not(in_state(FlappybirdState::MainTitle))),
+                ),
+        );
+    }
+}
@@ -0,0 +1,431 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    player::{
+        autopilot::{gaps_ahead, PilotView, PIPE_CLEARANCE},
+        bird_collision_groups,
+        controller::{BIRD_MASS, FLAP_IMPULSE, PLAYER_SPEED},
+        neural::{decide, features, load_genome, save_genome, Genome, GENOME_FILE},
+        skin::{ActiveSkin, Skin, SkinRegistry},
+        Player, BIRD_SCALE,
+    },
+    storage,
+    timestep::GameplaySet,
+    world::{pipes::Pipe, PipeSpawner, WorldRng},
+    FlappybirdState, Playfield,
+};
+
+const POPULATION: usize = 50;
+const ELITES: usize = 4;
+const TOURNAMENT: usize = 3;
+const MUTATION_RATE: f32 = 0.1;
+const MUTATION_SIZE: f32 = 0.5;
+const PIPE_FITNESS: f32 = 100.;
+// a generation that has learnt to fly forever still has to end
+const MAX_GENERATION_TICKS: u32 = 60 * 120;
+
+// One bird of the population, flown by the genome at `index`
+#[derive(Component)]
+pub struct Specimen {
+    pub index: usize,
+}
+
+#[derive(Component)]
+struct Passed;
+
+#[derive(Component)]
+struct EvolveHud;
+
+#[derive(Resource)]
+pub struct Evolution {
+    rng: ChaCha8Rng,
+    pub generation: u32,
+    population: Vec<Genome>,
+    fitness: Vec<f32>,
+    alive: Vec<bool>,
+    ticks: u32,
+    pipes_passed: u32,
+    pub best_fitness: f32,
+}
+
+impl Evolution {
+    pub fn alive_count(&self) -> usize {
+        self.alive.iter().filter(|alive| **alive).count()
+    }
+
+    fn fitness_now(&self) -> f32 {
+        self.ticks as f32 + self.pipes_passed as f32 * PIPE_FITNESS
+    }
+
+    fn tournament(&mut self) -> usize {
+        (0..TOURNAMENT)
+            .map(|_| self.rng.gen_range(0..self.population.len()))
+            .max_by(|a, b| self.fitness[*a].total_cmp(&self.fitness[*b]))
+            .unwrap_or(0)
+    }
+
+    // Keeps the elites as they are and fills the rest with mutated crossovers
+    fn breed(&mut self) {
+        let mut ranked: Vec<usize> = (0..self.population.len()).collect();
+        ranked.sort_by(|a, b| self.fitness[*b].total_cmp(&self.fitness[*a]));
+
+        let mut next: Vec<Genome> = ranked
+            .iter()
+            .take(ELITES)
+            .map(|index| self.population[*index].clone())
+            .collect();
+        while next.len() < POPULATION {
+            let mother = self.tournament();
+            let father = self.tournament();
+            let mut child = self.population[mother].clone();
+            for (weight, other) in child
+                .weights
+                .iter_mut()
+                .zip(&self.population[father].weights)
+            {
+                if self.rng.gen_bool(0.5) {
+                    *weight = *other;
+                }
+                if self.rng.gen::<f32>() < MUTATION_RATE {
+                    *weight += self.rng.gen_range(-MUTATION_SIZE..MUTATION_SIZE);
+                }
+            }
+            next.push(child);
+        }
+        self.population = next;
+        self.generation += 1;
+    }
+}
+
+fn start_evolution(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut player_query: Query<&mut Visibility, (With<Player>, Without<Specimen>)>,
+) {
+    for mut visibility in player_query.iter_mut() {
+        *visibility = Visibility::Hidden;
+    }
+
+    let mut rng = ChaCha8Rng::seed_from_u64(rand::thread_rng().gen());
+    let mut population: Vec<Genome> = (0..POPULATION).map(|_| Genome::random(&mut rng)).collect();
+    // carry on from the best bird of the last session, a generation has to beat it to
+    // replace it on disk
+    let mut best_fitness = 0.;
+    if let Some((genome, fitness)) = load_genome(&storage::data_dir().join(GENOME_FILE)) {
+        println!(
+            "Seeding the population with the saved genome, fitness {}",
+            fitness
+        );
+        population[0] = genome;
+        best_fitness = fitness;
+    }
+
+    commands.insert_resource(Evolution {
+        rng,
+        generation: 1,
+        population,
+        fitness: vec![0.; POPULATION],
+        alive: vec![false; POPULATION],
+        ticks: 0,
+        pipes_passed: 0,
+        best_fitness,
+    });
+
+    commands.spawn((
+        TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(12.),
+                left: Val::Px(12.),
+                ..default()
+            },
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("embedded://fonts/pixel.ttf"),
+                    font_size: 24.0,
+                    ..default()
+                },
+            ),
+            ..default()
+        },
+        EvolveHud,
+    ));
+}
+
+fn spawn_generation(
+    commands: &mut Commands,
+    evolution: &mut Evolution,
+    playfield: &Playfield,
+    pipe_query: &Query<Entity, With<Pipe>>,
+    spawner: &mut PipeSpawner,
+    skin: &Skin,
+) {
+    for entity in pipe_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    *spawner = PipeSpawner::default();
+    commands.insert_resource(WorldRng::new(evolution.rng.gen()));
+
+    evolution.fitness = vec![0.; evolution.population.len()];
+    evolution.alive = vec![true; evolution.population.len()];
+    evolution.ticks = 0;
+    evolution.pipes_passed = 0;
+
+    for index in 0..evolution.population.len() {
+        let mut transform = Transform::from_xyz(playfield.width / 6., playfield.height / 2., 2.);
+        transform.scale = Vec3::splat(BIRD_SCALE);
+        let animation_indices = skin.animation_indices();
+        commands.spawn((
+            SpriteBundle {
+                transform,
+                texture: skin.texture.clone(),
+                sprite: Sprite {
+                    color: Color::srgba(1., 1., 1., 0.6),
+                    ..default()
+                },
+                ..Default::default()
+            },
+            TextureAtlas {
+                layout: skin.layout.clone(),
+                index: animation_indices.first,
+            },
+            animation_indices,
+            skin.animation_timer(),
+            Player::default(),
+            Specimen { index },
+            RigidBody::Dynamic,
+            ExternalImpulse::default(),
+            ColliderMassProperties::Density(0.),
+            AdditionalMassProperties::Mass(BIRD_MASS),
+            Velocity::default(),
+            skin.manifest.collider.collider(),
+            bird_collision_groups(),
+        ));
+    }
+}
+
+// Every specimen asks its own network whether to flap
+fn think(
+    evolution: Res<Evolution>,
+    playfield: Res<Playfield>,
+    mut specimen_query: Query<(
+        &Specimen,
+        &mut Transform,
+        &mut Velocity,
+        &mut ExternalImpulse,
+    )>,
+    pipe_query: Query<&Transform, (With<Pipe>, Without<Specimen>)>,
+) {
+    for (specimen, mut transform, mut velocity, mut impulse) in specimen_query.iter_mut() {
+        velocity.linvel.x = 0.;
+        velocity.linvel.y = velocity.linvel.y.min(PLAYER_SPEED);
+        transform.rotation = Quat::from_rotation_z((velocity.linvel.y / 600.).clamp(-1.5, 0.45));
+
+        let bird = transform.translation.truncate();
+        let view = PilotView {
+            bird,
+            velocity: velocity.linvel,
+            gaps: gaps_ahead(bird, pipe_query.iter()),
+            playfield: *playfield,
+        };
+        let genome = &evolution.population[specimen.index];
+        if bird.y < playfield.height && decide(genome, &features(&view)) {
+            if velocity.linvel.y < 0. {
+                velocity.linvel.y = 0.;
+            }
+            impulse.impulse = Vec2::new(0., FLAP_IMPULSE);
+        }
+    }
+}
+
+// The whole population flies in one column, so a pipe passed by one is passed by all
+fn count_passed_pipes(
+    mut commands: Commands,
+    mut evolution: ResMut<Evolution>,
+    playfield: Res<Playfield>,
+    pipe_query: Query<(Entity, &Transform), (With<Pipe>, Without<Passed>)>,
+) {
+    evolution.ticks += 1;
+    let column = playfield.width / 6.;
+    for (entity, transform) in pipe_query.iter() {
+        if transform.translation.x + PIPE_CLEARANCE < column {
+            evolution.pipes_passed += 1;
+            commands.entity(entity).insert(Passed);
+        }
+    }
+}
+
+fn cull_specimens(
+    mut commands: Commands,
+    mut evolution: ResMut<Evolution>,
+    mut collision_events: EventReader<CollisionEvent>,
+    specimen_query: Query<&Specimen>,
+    all_specimens: Query<(Entity, &Specimen)>,
+    pipe_query: Query<Entity, With<Pipe>>,
+) {
+    let fitness = evolution.fitness_now();
+    for event in collision_events.read() {
+        let CollisionEvent::Started(entity1, entity2, _) = event else {
+            continue;
+        };
+        for (bird, other) in [(entity1, entity2), (entity2, entity1)] {
+            let Ok(specimen) = specimen_query.get(*bird) else {
+                continue;
+            };
+            // flying through a gap's score sensor is not a crash
+            if pipe_query.get(*other).is_ok() || !evolution.alive[specimen.index] {
+                continue;
+            }
+            evolution.alive[specimen.index] = false;
+            evolution.fitness[specimen.index] = fitness;
+            commands.entity(*bird).despawn_recursive();
+        }
+    }
+
+    if evolution.ticks >= MAX_GENERATION_TICKS {
+        for (entity, specimen) in all_specimens.iter() {
+            if evolution.alive[specimen.index] {
+                evolution.alive[specimen.index] = false;
+                evolution.fitness[specimen.index] = fitness;
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+fn next_generation(
+    mut commands: Commands,
+    mut evolution: ResMut<Evolution>,
+    playfield: Res<Playfield>,
+    pipe_query: Query<Entity, With<Pipe>>,
+    mut spawner: ResMut<PipeSpawner>,
+    registry: Res<SkinRegistry>,
+    active_skin: Res<ActiveSkin>,
+) {
+    if evolution.alive_count() > 0 {
+        return;
+    }
+    // the first generation has nothing to breed from yet
+    if evolution.ticks > 0 {
+        let (best, fitness) = evolution
+            .fitness
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(index, fitness)| (index, *fitness))
+            .unwrap_or((0, 0.));
+        println!(
+            "Generation {} best fitness {}",
+            evolution.generation, fitness
+        );
+        if fitness > evolution.best_fitness {
+            evolution.best_fitness = fitness;
+            save_genome(&evolution.population[best], evolution.generation, fitness);
+            println!(
+                "Saved the new best genome to {}",
+                storage::data_dir().join(GENOME_FILE).display()
+            );
+        }
+        evolution.breed();
+    }
+    spawn_generation(
+        &mut commands,
+        &mut evolution,
+        &playfield,
+        &pipe_query,
+        &mut spawner,
+        registry.get(active_skin.0),
+    );
+}
+
+fn update_evolve_hud(evolution: Res<Evolution>, mut hud_query: Query<&mut Text, With<EvolveHud>>) {
+    for mut text in hud_query.iter_mut() {
+        text.sections[0].value = format!(
+            "GENERATION {}\nALIVE {}/{}\nBEST {}",
+            evolution.generation,
+            evolution.alive_count(),
+            evolution.population.len(),
+            evolution.best_fitness.round()
+        );
+    }
+}
+
+fn leave_evolve(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut state: ResMut<NextState<FlappybirdState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        state.set(FlappybirdState::MainTitle);
+    }
+}
+
+fn stop_evolution(
+    mut commands: Commands,
+    specimen_query: Query<Entity, Or<(With<Specimen>, With<EvolveHud>, With<Pipe>)>>,
+    mut player_query: Query<&mut Visibility, (With<Player>, Without<Specimen>)>,
+    mut spawner: ResMut<PipeSpawner>,
+) {
+    for entity in specimen_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    for mut visibility in player_query.iter_mut() {
+        *visibility = Visibility::Inherited;
+    }
+    *spawner = PipeSpawner::default();
+    commands.remove_resource::<Evolution>();
+}
+
+pub struct EvolvePlugin;
+
+impl Plugin for EvolvePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(FlappybirdState::Evolve), start_evolution)
+            .add_systems(OnExit(FlappybirdState::Evolve), stop_evolution)
+            .add_systems(
+                FixedUpdate,
+                (
+                    (think, count_passed_pipes).in_set(GameplaySet::Simulation),
+                    (cull_specimens, next_generation)
+                        .chain()
+                        .in_set(GameplaySet::Collisions),
+                )
+                    .run_if(
+                        in_state(FlappybirdState::Evolve).and_then(resource_exists::<Evolution>),
+                    ),
+            )
+            .add_systems(
+                Update,
+                (update_evolve_hud, leave_evolve).run_if(
+                    in_state(FlappybirdState::Evolve).and_then(resource_exists::<Evolution>),
+                ),
+            );
+    }
+}
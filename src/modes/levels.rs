@@ -0,0 +1,816 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::{collections::BTreeMap, f32::consts::TAU, fmt};
+
+use bevy::{
+    asset::{io::Reader, AssetLoadFailedEvent, AssetLoader, AsyncReadExt, LoadContext, LoadState},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    player::{controller::Flapped, Player},
+    sfx::{PlaySfx, Sfx},
+    storage,
+    timestep::GameplaySet,
+    ui::{
+        gameover::{GameOverButtons, GameOverUI},
+        titlescreen::{reset_title_scene, spawn_text_button, start_run},
+    },
+    world::{
+        difficulty::reset_difficulty,
+        pipes::{spawn_pipe, spawn_pipes, FinishLine},
+        WorldScroll, WorldSpeed,
+    },
+    FlappybirdState, Playfield,
+};
+
+use super::GameMode;
+
+// Levels shipped with the game, in the order the select screen lists them
+const LEVELS: [&str; 3] = [
+    "embedded://levels/first-flight.level.ron",
+    "embedded://levels/staircase.level.ron",
+    "embedded://levels/moving-day.level.ron",
+];
+const LEVELS_FILE: &str = "levels.ron";
+const LEVELS_VERSION: u32 = 1;
+
+// Bobs a pipe up and down around its gap center
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct PipeMotion {
+    pub amplitude: f32,
+    // seconds for a full swing up and back
+    pub period: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LevelPipe {
+    // distance from the start of the level
+    pub x: f32,
+    // pixels above the middle of the playfield, negative is below
+    pub gap_center: f32,
+    // the opening in sprite pixels, like the difficulty curves
+    pub gap: f32,
+    #[serde(default)]
+    pub motion: Option<PipeMotion>,
+}
+
+// A .level.ron file, pipes in the order they come
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct Level {
+    pub name: String,
+    pub speed: f32,
+    pub pipes: Vec<LevelPipe>,
+    // distance from the start, crossing it alive completes the level
+    pub finish: f32,
+    // most flaps a finish can take for three stars and for two, any finish is worth one
+    pub star_flaps: (u32, u32),
+}
+
+impl Level {
+    // Anything the level runner can't lay out or scroll through is turned away
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.speed.is_finite() || self.speed <= 0. {
+            return Err(format!("speed {} has to be positive", self.speed));
+        }
+        for (index, pipe) in self.pipes.iter().enumerate() {
+            if !pipe.x.is_finite() || !pipe.gap_center.is_finite() {
+                return Err(format!("pipe {} is not at a number", index + 1));
+            }
+            if !pipe.gap.is_finite() || pipe.gap <= 0. {
+                return Err(format!("pipe {} has a gap of {}", index + 1, pipe.gap));
+            }
+            if let Some(motion) = pipe.motion {
+                if !motion.amplitude.is_finite()
+                    || !motion.period.is_finite()
+                    || motion.period <= 0.
+                {
+                    return Err(format!(
+                        "pipe {} swings {} pixels every {} seconds",
+                        index + 1,
+                        motion.amplitude,
+                        motion.period
+                    ));
+                }
+            }
+        }
+        // the runner spawns them as the bird reaches them, one after the other
+        if let Some(index) = self
+            .pipes
+            .windows(2)
+            .position(|pair| pair[1].x <= pair[0].x)
+        {
+            return Err(format!(
+                "pipe {} at {} comes after pipe {} at {}",
+                index + 2,
+                self.pipes[index + 1].x,
+                index + 1,
+                self.pipes[index].x
+            ));
+        }
+        let last = self.pipes.last().map_or(0., |pipe| pipe.x);
+        if !self.finish.is_finite() || self.finish <= last {
+            return Err(format!(
+                "the finish at {} has to be past the last pipe at {}",
+                self.finish, last
+            ));
+        }
+        let (three, two) = self.star_flaps;
+        if three > two {
+            return Err(format!(
+                "three stars allow {} flaps but two only {}",
+                three, two
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn stars(&self, flaps: u32) -> u8 {
+        let (three, two) = self.star_flaps;
+        if flaps <= three {
+            3
+        } else if flaps <= two {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LevelLoadError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    Invalid(String),
+}
+
+impl fmt::Display for LevelLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelLoadError::Io(err) => write!(f, "{}", err),
+            LevelLoadError::Ron(err) => write!(f, "not a valid level: {}", err),
+            LevelLoadError::Invalid(reason) => write!(f, "not a valid level: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for LevelLoadError {}
+
+impl From<std::io::Error> for LevelLoadError {
+    fn from(err: std::io::Error) -> Self {
+        LevelLoadError::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for LevelLoadError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        LevelLoadError::Ron(err)
+    }
+}
+
+fn parse_level(bytes: &[u8]) -> Result<Level, LevelLoadError> {
+    let level: Level = ron::de::from_bytes(bytes)?;
+    level.validate().map_err(LevelLoadError::Invalid)?;
+    Ok(level)
+}
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    type Asset = Level;
+    type Settings = ();
+    type Error = LevelLoadError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Level, LevelLoadError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        parse_level(&bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct LevelLibrary {
+    pub levels: Vec<Handle<Level>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LevelRecord {
+    // pipes passed on the best try, finished or not
+    pub best: u64,
+    pub stars: u8,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LevelsFile {
+    version: u32,
+    levels: BTreeMap<String, LevelRecord>,
+}
+
+// Best score and stars per level, keyed by level name
+#[derive(Resource, Debug, Default)]
+pub struct LevelRecords {
+    pub levels: BTreeMap<String, LevelRecord>,
+}
+
+impl LevelRecords {
+    pub fn load() -> Self {
+        match storage::load::<LevelsFile>(LEVELS_FILE) {
+            Some(file) if file.version == LEVELS_VERSION => Self {
+                levels: file.levels,
+            },
+            Some(file) => {
+                eprintln!(
+                    "Ignoring level records with unknown version {}",
+                    file.version
+                );
+                Self::default()
+            }
+            None => Self::default(),
+        }
+    }
+
+    pub fn save(&self) {
+        storage::save(
+            LEVELS_FILE,
+            &LevelsFile {
+                version: LEVELS_VERSION,
+                levels: self.levels.clone(),
+            },
+        );
+    }
+
+    pub fn record(&self, name: &str) -> LevelRecord {
+        self.levels.get(name).cloned().unwrap_or_default()
+    }
+}
+
+// The level picked on the select screen, an index into the library
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct ActiveLevel(pub usize);
+
+// How far into the level the current try is
+#[derive(Resource, Debug, Default)]
+struct LevelProgress {
+    distance: f32,
+    next_pipe: usize,
+    finish_spawned: bool,
+    flaps: u32,
+    completed: bool,
+}
+
+#[derive(Component)]
+struct MovingPipe {
+    center: f32,
+    motion: PipeMotion,
+    elapsed: f32,
+}
+
+#[derive(Component)]
+struct LevelSelectUI;
+
+#[derive(Component)]
+struct LevelSelectBackButton;
+
+#[derive(Component)]
+struct LevelButton(usize);
+
+// Where the levels go on the select screen, filled in as they load
+#[derive(Component)]
+struct LevelList;
+
+fn load_levels(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(LevelLibrary {
+        levels: LEVELS.iter().map(|path| asset_server.load(*path)).collect(),
+    });
+}
+
+fn stars_label(stars: u8) -> String {
+    format!("{}/3 STARS", stars)
+}
+
+fn spawn_level_select_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(20.0),
+                ..default()
+            },
+            background_color: Color::srgba(0., 0., 0., 0.6).into(),
+            ..default()
+        })
+        .insert(LevelSelectUI)
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(20.0),
+                        left: Val::Px(20.0),
+                        width: Val::Px(7. * 3.),
+                        height: Val::Px(10. * 3.),
+                        ..default()
+                    },
+                    image: UiImage::new(asset_server.load("embedded://sprites/ui/back.png")),
+                    ..Default::default()
+                })
+                .insert(LevelSelectBackButton);
+
+            parent.spawn(TextBundle::from_section(
+                "LEVELS",
+                TextStyle {
+                    font: asset_server.load("embedded://fonts/pixel.ttf"),
+                    font_size: 48.0,
+                    ..default()
+                },
+            ));
+
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(20.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                LevelList,
+            ));
+        });
+}
+
+// Lists the levels once the screen is up and again each time one loads or fails to
+fn update_level_list(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    library: Res<LevelLibrary>,
+    levels: Res<Assets<Level>>,
+    records: Res<LevelRecords>,
+    mut level_events: EventReader<AssetEvent<Level>>,
+    mut failed_events: EventReader<AssetLoadFailedEvent<Level>>,
+    list_query: Query<(Entity, Ref<LevelList>)>,
+) {
+    let changed = level_events.read().count() + failed_events.read().count() > 0;
+    let Ok((list, marker)) = list_query.get_single() else {
+        return;
+    };
+    if !changed && !marker.is_added() {
+        return;
+    }
+    let text_style = TextStyle {
+        font: asset_server.load("embedded://fonts/pixel.ttf"),
+        font_size: 24.0,
+        ..default()
+    };
+    commands
+        .entity(list)
+        .despawn_descendants()
+        .with_children(|parent| {
+            for (index, handle) in library.levels.iter().enumerate() {
+                let Some(level) = levels.get(handle) else {
+                    let status = match asset_server.load_state(handle) {
+                        LoadState::Failed(_) => "FAILED TO LOAD",
+                        _ => "LOADING",
+                    };
+                    parent.spawn(TextBundle::from_section(
+                        format!("{}. {}", index + 1, status),
+                        text_style.clone(),
+                    ));
+                    continue;
+                };
+                let record = records.record(&level.name);
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(16.0),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        spawn_text_button(
+                            parent,
+                            &asset_server,
+                            &format!("{}. {}", index + 1, level.name),
+                            LevelButton(index),
+                        );
+                        parent.spawn(TextBundle::from_section(
+                            format!("{}  BEST {}", stars_label(record.stars), record.best),
+                            text_style.clone(),
+                        ));
+                    });
+            }
+        });
+}
+
+fn report_level_errors(mut failed_events: EventReader<AssetLoadFailedEvent<Level>>) {
+    for event in failed_events.read() {
+        eprintln!("Failed to load level {}: {}", event.path, event.error);
+    }
+}
+
+fn despawn_level_select_screen(mut commands: Commands, query: Query<Entity, With<LevelSelectUI>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn level_select_button_system(
+    mut commands: Commands,
+    level_query: Query<(&Interaction, &LevelButton), Changed<Interaction>>,
+    back_query: Query<&Interaction, (Changed<Interaction>, With<LevelSelectBackButton>)>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    asset_server: Res<AssetServer>,
+    mut sfx: EventWriter<PlaySfx>,
+    mut state: ResMut<NextState<FlappybirdState>>,
+    mut mode: ResMut<GameMode>,
+    mut player_query: Query<&mut Transform, With<Player>>,
+    playfield: Res<Playfield>,
+) {
+    for (interaction, button) in level_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        println!("Level {} clicked!", button.0 + 1);
+        sfx.send(PlaySfx(Sfx::Swooshing));
+        let Ok(mut transform) = player_query.get_single_mut() else {
+            return;
+        };
+        commands.insert_resource(ActiveLevel(button.0));
+        *mode = GameMode::Level;
+        start_run(
+            &mut commands,
+            &asset_server,
+            &mut state,
+            &mut transform,
+            &playfield,
+        );
+        return;
+    }
+
+    let back_pressed = back_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+    if back_pressed || keyboard_input.just_pressed(KeyCode::Escape) {
+        println!("Level select back clicked!");
+        sfx.send(PlaySfx(Sfx::Swooshing));
+        state.set(FlappybirdState::MainTitle);
+    }
+}
+
+fn active_level<'a>(
+    active: Option<Res<ActiveLevel>>,
+    library: &LevelLibrary,
+    levels: &'a Assets<Level>,
+) -> Option<&'a Level> {
+    levels.get(library.levels.get(active?.0)?)
+}
+
+fn start_level(
+    mut commands: Commands,
+    mut speed: ResMut<WorldSpeed>,
+    active: Option<Res<ActiveLevel>>,
+    library: Res<LevelLibrary>,
+    levels: Res<Assets<Level>>,
+) {
+    commands.insert_resource(LevelProgress::default());
+    if let Some(level) = active_level(active, &library, &levels) {
+        println!("Playing level {}", level.name);
+        speed.0 = level.speed;
+    }
+}
+
+// Brings in the pipes and the finish line as their distance comes up, a pipe at
+// distance x reaches the bird when the level has scrolled x
+fn advance_level(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    scroll: Res<WorldScroll>,
+    playfield: Res<Playfield>,
+    active: Option<Res<ActiveLevel>>,
+    library: Res<LevelLibrary>,
+    levels: Res<Assets<Level>>,
+    mut progress: ResMut<LevelProgress>,
+    mut state: ResMut<NextState<FlappybirdState>>,
+) {
+    let Some(level) = active_level(active, &library, &levels) else {
+        return;
+    };
+    progress.distance += scroll.delta;
+    let bird_x = playfield.width / 6.;
+    let horizon = playfield.width + 50.;
+
+    while let Some(pipe) = level.pipes.get(progress.next_pipe) {
+        let x = bird_x + pipe.x - progress.distance;
+        if x > horizon {
+            break;
+        }
+        let center = playfield.height / 2. + pipe.gap_center;
+        let entity = spawn_pipe(&mut commands, &asset_server, Vec2::new(x, center), pipe.gap);
+        if let Some(motion) = pipe.motion {
+            commands.entity(entity).insert(MovingPipe {
+                center,
+                motion,
+                elapsed: 0.,
+            });
+        }
+        progress.next_pipe += 1;
+    }
+
+    let finish_x = bird_x + level.finish - progress.distance;
+    if !progress.finish_spawned && finish_x <= horizon {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::srgba(1., 1., 1., 0.5),
+                    custom_size: Some(Vec2::new(12., playfield.height)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(finish_x, playfield.height / 2., 1.),
+                ..default()
+            },
+            FinishLine,
+        ));
+        progress.finish_spawned = true;
+    }
+
+    if progress.distance >= level.finish && !progress.completed {
+        println!("Level {} completed", level.name);
+        progress.completed = true;
+        state.set(FlappybirdState::GameOver);
+    }
+}
+
+fn move_level_pipes(time: Res<Time>, mut pipe_query: Query<(&mut Transform, &mut MovingPipe)>) {
+    for (mut transform, mut moving) in pipe_query.iter_mut() {
+        moving.elapsed += time.delta_seconds();
+        let phase = moving.elapsed / moving.motion.period * TAU;
+        transform.translation.y = moving.center + moving.motion.amplitude * phase.sin();
+    }
+}
+
+fn count_level_flaps(mut flapped: EventReader<Flapped>, progress: Option<ResMut<LevelProgress>>) {
+    let count = flapped.read().count() as u32;
+    if let Some(mut progress) = progress {
+        progress.flaps += count;
+    }
+}
+
+// Records the try and shows how it went, stars only come with a finish
+fn show_level_result(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    player_query: Query<&Player>,
+    active: Option<Res<ActiveLevel>>,
+    library: Res<LevelLibrary>,
+    levels: Res<Assets<Level>>,
+    progress: Option<Res<LevelProgress>>,
+    mut records: ResMut<LevelRecords>,
+) {
+    let Some(level) = active_level(active, &library, &levels) else {
+        return;
+    };
+    let score = player_query.get_single().map_or(0, |player| player.score);
+    let (completed, flaps) =
+        progress.map_or((false, 0), |progress| (progress.completed, progress.flaps));
+    let stars = if completed { level.stars(flaps) } else { 0 };
+
+    let record = records.levels.entry(level.name.clone()).or_default();
+    if score > record.best || stars > record.stars {
+        record.best = record.best.max(score);
+        record.stars = record.stars.max(stars);
+        records.save();
+    }
+
+    let headline = if completed {
+        "LEVEL COMPLETE"
+    } else {
+        "LEVEL FAILED"
+    };
+    let details = if completed {
+        format!("{}  {} FLAPS", stars_label(stars), flaps)
+    } else {
+        format!("{} OF {} PIPES", score, level.pipes.len())
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(20.0),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(GameOverUI)
+        .with_children(|parent| {
+            for (text, font_size) in [(headline.to_string(), 60.0), (details, 30.0)] {
+                parent.spawn(
+                    TextBundle::from_section(
+                        text,
+                        TextStyle {
+                            font: asset_server.load("embedded://fonts/pixel.ttf"),
+                            font_size,
+                            ..default()
+                        },
+                    )
+                    .with_text_justify(JustifyText::Center),
+                );
+            }
+
+            // Buttons node
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Auto,
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        flex_direction: FlexDirection::Row,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn(ButtonBundle {
+                            style: Style {
+                                width: Val::Px(31. * 3.),
+                                height: Val::Px(18. * 3.),
+                                margin: UiRect::horizontal(Val::Px(10.0)),
+                                ..default()
+                            },
+                            image: UiImage::new(
+                                asset_server.load("embedded://sprites/ui/play.png"),
+                            ),
+                            ..Default::default()
+                        })
+                        .insert(GameOverButtons::Play);
+                    spawn_text_button(parent, &asset_server, "LEVELS", GameOverButtons::Levels);
+                    spawn_text_button(parent, &asset_server, "MENU", GameOverButtons::Menu);
+                });
+        });
+}
+
+pub struct LevelsPlugin;
+
+impl Plugin for LevelsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Level>()
+            .init_asset_loader::<LevelLoader>()
+            .insert_resource(LevelRecords::load())
+            .add_systems(Startup, load_levels)
+            // the score readout from the last try goes, the bird waits in the middle
+            .add_systems(
+                OnEnter(FlappybirdState::LevelSelect),
+                (reset_title_scene, spawn_level_select_screen),
+            )
+            .add_systems(
+                OnExit(FlappybirdState::LevelSelect),
+                despawn_level_select_screen,
+            )
+            .add_systems(
+                Update,
+                (update_level_list, level_select_button_system)
+                    .run_if(in_state(FlappybirdState::LevelSelect)),
+            )
+            .add_systems(Update, report_level_errors)
+            .add_systems(
+                OnEnter(FlappybirdState::TapTap),
+                start_level
+                    .after(reset_difficulty)
+                    .run_if(resource_equals(GameMode::Level)),
+            )
+            .add_systems(
+                FixedUpdate,
+                // after the pipes have moved, so the new ones start where they belong
+                (advance_level.after(spawn_pipes), move_level_pipes)
+                    .in_set(GameplaySet::Simulation)
+                    .run_if(in_state(FlappybirdState::InGame))
+                    .run_if(resource_equals(GameMode::Level)),
+            )
+            .add_systems(
+                FixedUpdate,
+                count_level_flaps
+                    .in_set(GameplaySet::Collisions)
+                    .run_if(resource_equals(GameMode::Level)),
+            )
+            .add_systems(
+                OnEnter(FlappybirdState::GameOver),
+                show_level_result.run_if(resource_equals(GameMode::Level)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIRST_FLIGHT: &str = include_str!("../../assets/levels/first-flight.level.ron");
+
+    fn rejects(edit: impl FnOnce(&mut String)) -> String {
+        let mut source = FIRST_FLIGHT.to_string();
+        edit(&mut source);
+        match parse_level(source.as_bytes()) {
+            Err(LevelLoadError::Invalid(reason)) => reason,
+            other => panic!("loaded {:?}", other.map(|level| level.name)),
+        }
+    }
+
+    #[test]
+    fn shipped_levels_load() {
+        for source in [
+            FIRST_FLIGHT,
+            include_str!("../../assets/levels/staircase.level.ron"),
+            include_str!("../../assets/levels/moving-day.level.ron"),
+        ] {
+            if let Err(err) = parse_level(source.as_bytes()) {
+                panic!("{}", err);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_a_pipe_that_never_swings() {
+        rejects(|source| {
+            *source = source.replacen(
+                "(x: 950.0, gap_center: 40.0, gap: 100.0)",
+                "(x: 950.0, gap_center: 40.0, gap: 100.0, motion: Some((amplitude: 40.0, period: 0.0)))",
+                1,
+            )
+        });
+    }
+
+    #[test]
+    fn rejects_pipes_out_of_order() {
+        rejects(|source| *source = source.replacen("x: 1200.0", "x: 900.0", 1));
+    }
+
+    #[test]
+    fn rejects_a_standing_course() {
+        rejects(|source| *source = source.replacen("speed: 130.0", "speed: 0.0", 1));
+        rejects(|source| *source = source.replacen("speed: 130.0", "speed: -130.0", 1));
+    }
+
+    #[test]
+    fn rejects_a_closed_gap() {
+        rejects(|source| *source = source.replacen("gap: 96.0", "gap: 0.0", 1));
+        rejects(|source| *source = source.replacen("gap: 96.0", "gap: -20.0", 1));
+    }
+
+    #[test]
+    fn rejects_a_finish_before_the_last_pipe() {
+        rejects(|source| *source = source.replacen("finish: 2800.0", "finish: 2000.0", 1));
+    }
+
+    #[test]
+    fn rejects_stars_the_wrong_way_round() {
+        rejects(|source| {
+            *source = source.replacen("star_flaps: (22, 30)", "star_flaps: (30, 22)", 1)
+        });
+    }
+}
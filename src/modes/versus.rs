@@ -0,0 +1,237 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    player::{
+        bird_collision_groups,
+        controller::{FlapIntent, BIRD_MASS},
+        skin::{ActiveSkin, SkinRegistry},
+        Eliminated, Player, Seat, BIRD_SCALE,
+    },
+    timestep::RenderInterpolation,
+    ui::{
+        gameover::{GameOverButtons, GameOverUI},
+        score::{spawn_score_ui, spawn_seat_score_ui, ScoreUI},
+        titlescreen::{reset_title_scene, spawn_text_button},
+    },
+    world::seed_world_rng,
+    FlappybirdState, Playfield,
+};
+
+use super::GameMode;
+
+// Player two wears the same skin, tinted so the two can be told apart
+const RIVAL_TINT: Color = Color::srgb(1., 0.55, 0.55);
+
+// Player two only exists while a versus run is being played
+fn sync_rival(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mode: Res<GameMode>,
+    playfield: Res<Playfield>,
+    registry: Res<SkinRegistry>,
+    active_skin: Res<ActiveSkin>,
+    bird_query: Query<(Entity, &Seat), With<Player>>,
+    score_query: Query<Entity, With<ScoreUI>>,
+) {
+    let rival = bird_query.iter().find(|(_, seat)| seat.0 == 1);
+    match (*mode == GameMode::Versus, rival) {
+        (true, None) => {
+            let skin = registry.get(active_skin.0);
+            let mut transform =
+                Transform::from_xyz(playfield.width / 6., playfield.height / 2., 2.);
+            transform.scale = Vec3::splat(BIRD_SCALE);
+            let animation_indices = skin.animation_indices();
+            commands.spawn((
+                SpriteBundle {
+                    transform,
+                    texture: skin.texture.clone(),
+                    sprite: Sprite {
+                        color: RIVAL_TINT,
+                        ..default()
+                    },
+                    ..Default::default()
+                },
+                TextureAtlas {
+                    layout: skin.layout.clone(),
+                    index: animation_indices.first,
+                },
+                Player {
+                    name: "player two".to_string(),
+                    ..Default::default()
+                },
+                Seat(1),
+                animation_indices,
+                RigidBody::Fixed,
+                ExternalImpulse::default(),
+                ColliderMassProperties::Density(0.),
+                AdditionalMassProperties::Mass(BIRD_MASS),
+                Velocity::default(),
+                skin.manifest.collider.collider(),
+                bird_collision_groups(),
+                skin.animation_timer(),
+                FlapIntent::default(),
+                RenderInterpolation::default(),
+            ));
+
+            // the centred readout from the title screen makes way for one per player
+            for entity in score_query.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+            spawn_seat_score_ui(&mut commands, &asset_server, Seat(0), Val::Percent(-25.));
+            spawn_seat_score_ui(&mut commands, &asset_server, Seat(1), Val::Percent(25.));
+        }
+        (false, Some((entity, _))) => {
+            commands.entity(entity).despawn_recursive();
+            for entity in score_query.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+            spawn_score_ui(&mut commands, &asset_server);
+        }
+        _ => {}
+    }
+}
+
+fn despawn_rival(mut commands: Commands, bird_query: Query<(Entity, &Seat), With<Player>>) {
+    for (entity, seat) in bird_query.iter() {
+        if seat.0 != 0 {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+// The last bird in the air wins, birds that crash on the same tick are split by score
+fn show_winner_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    bird_query: Query<(&Player, &Seat, Option<&Eliminated>)>,
+) {
+    let mut standings: Vec<(u32, u64, usize)> = bird_query
+        .iter()
+        .map(|(player, seat, eliminated)| {
+            (
+                eliminated.map_or(u32::MAX, |eliminated| eliminated.tick),
+                player.score,
+                seat.0,
+            )
+        })
+        .collect();
+    standings.sort_by(|a, b| (b.0, b.1).cmp(&(a.0, a.1)));
+    let headline = match standings.as_slice() {
+        [first, second, ..] if (first.0, first.1) == (second.0, second.1) => "DRAW".to_string(),
+        [first, ..] => format!("PLAYER {} WINS", first.2 + 1),
+        [] => "DRAW".to_string(),
+    };
+    println!("Versus over: {}", headline);
+
+    standings.sort_by_key(|standing| standing.2);
+    let scores = standings
+        .iter()
+        .map(|(_, score, seat)| format!("P{} {}", seat + 1, score))
+        .collect::<Vec<_>>()
+        .join("   ");
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(20.0),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(GameOverUI)
+        .with_children(|parent| {
+            for (text, font_size) in [(headline, 60.0), (scores, 30.0)] {
+                parent.spawn(
+                    TextBundle::from_section(
+                        text,
+                        TextStyle {
+                            font: asset_server.load("embedded://fonts/pixel.ttf"),
+                            font_size,
+                            ..default()
+                        },
+                    )
+                    .with_text_justify(JustifyText::Center),
+                );
+            }
+
+            // Buttons node
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Auto,
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        flex_direction: FlexDirection::Row,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn(ButtonBundle {
+                            style: Style {
+                                width: Val::Px(31. * 3.),
+                                height: Val::Px(18. * 3.),
+                                margin: UiRect::horizontal(Val::Px(10.0)),
+                                ..default()
+                            },
+                            image: UiImage::new(
+                                asset_server.load("embedded://sprites/ui/play.png"),
+                            ),
+                            ..Default::default()
+                        })
+                        .insert(GameOverButtons::Play);
+                    spawn_text_button(parent, &asset_server, "MENU", GameOverButtons::Menu);
+                });
+        });
+}
+
+pub struct VersusPlugin;
+
+impl Plugin for VersusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(FlappybirdState::TapTap),
+            sync_rival.before(seed_world_rng),
+        )
+        .add_systems(
+            OnEnter(FlappybirdState::MainTitle),
+            despawn_rival.before(reset_title_scene),
+        )
+        .add_systems(
+            OnEnter(FlappybirdState::GameOver),
+            show_winner_screen.run_if(resource_equals(GameMode::Versus)),
+        );
+    }
+}
@@ -0,0 +1,338 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    asset::{io::AssetSource, AssetApp, LoadState},
+    prelude::*,
+};
+use bevy_kira_audio::AudioSource;
+
+use crate::{
+    player::skin::{load_skins, select_skin, SkinManifest, SkinRegistry},
+    storage,
+};
+
+pub const MODS_SOURCE: &str = "mods";
+const MODS_LOG: &str = "mods.log";
+// Mod skins live where the built-in ones do, in their own folder with a skin manifest
+const SKINS_DIR: &str = "sprites/birds";
+const SKIN_MANIFEST: &str = "skin.ron";
+
+// Files here replace the embedded asset with the same path, sky textures included
+pub fn mods_dir() -> PathBuf {
+    storage::data_dir().join("mods")
+}
+
+// Problems with a mod are never fatal, they go to stderr and to mods.log next to the mods
+fn report(message: &str) {
+    eprintln!("{}", message);
+    let path = mods_dir().join(MODS_LOG);
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{}", message));
+    if let Err(err) = written {
+        eprintln!("Failed to write {}: {}", path.display(), err);
+    }
+}
+
+// Relative, forward slashed paths of every file under `dir`
+fn mod_files(dir: &Path, root: &Path, files: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            mod_files(&path, root, files);
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            let relative = relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            if relative != MODS_LOG {
+                files.push(relative);
+            }
+        }
+    }
+}
+
+// Reads the size out of a PNG header without decoding the image
+fn png_size(path: &Path) -> Option<UVec2> {
+    let bytes = fs::read(path).ok()?;
+    if bytes.len() < 24 || &bytes[1..4] != b"PNG" {
+        return None;
+    }
+    let width = u32::from_be_bytes(bytes[16..20].try_into().ok()?);
+    let height = u32::from_be_bytes(bytes[20..24].try_into().ok()?);
+    Some(UVec2::new(width, height))
+}
+
+// An asset type a mod can replace, with what it takes for the replacement to fit
+pub trait ModAsset: Asset {
+    fn validate(_modded: &Self, _embedded: &Self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+// Sprite sheets are cut into frames by size, so a replacement has to match exactly
+impl ModAsset for Image {
+    fn validate(modded: &Self, embedded: &Self) -> Result<(), String> {
+        if modded.size() == embedded.size() {
+            Ok(())
+        } else {
+            Err(format!(
+                "is {}x{} but has to be {}x{}",
+                modded.size().x,
+                modded.size().y,
+                embedded.size().x,
+                embedded.size().y
+            ))
+        }
+    }
+}
+
+impl ModAsset for Font {}
+
+impl ModAsset for AudioSource {}
+
+struct Override<A: Asset> {
+    path: String,
+    modded: Handle<A>,
+    embedded: Handle<A>,
+}
+
+// Both sides of an override are loaded before the mod is checked and swapped in
+#[derive(Resource)]
+struct PendingOverrides<A: Asset>(Vec<Override<A>>);
+
+impl<A: Asset> Default for PendingOverrides<A> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+// Embedded handles now holding a mod's asset, kept so the override is never unloaded
+#[derive(Resource, Default)]
+struct AppliedOverrides(Vec<UntypedHandle>);
+
+fn queue_override<A: Asset>(
+    asset_server: &AssetServer,
+    pending: &mut Vec<Override<A>>,
+    path: &str,
+) {
+    pending.push(Override {
+        path: path.to_string(),
+        modded: asset_server.load(format!("{}://{}", MODS_SOURCE, path)),
+        embedded: asset_server.load(format!("embedded://{}", path)),
+    });
+}
+
+fn scan_mods(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let dir = mods_dir();
+    if !dir.is_dir() {
+        return;
+    }
+    println!("Loading mods from {}", dir.display());
+    let _ = fs::remove_file(dir.join(MODS_LOG));
+
+    let mut files = Vec::new();
+    mod_files(&dir, &dir, &mut files);
+    files.sort();
+
+    let mut images = PendingOverrides::<Image>::default();
+    let mut fonts = PendingOverrides::<Font>::default();
+    let mut sounds = PendingOverrides::<AudioSource>::default();
+    for path in files.iter() {
+        match Path::new(path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("png") => queue_override(&asset_server, &mut images.0, path),
+            Some("ttf") => queue_override(&asset_server, &mut fonts.0, path),
+            Some("ogg") => queue_override(&asset_server, &mut sounds.0, path),
+            Some("ron") if path.ends_with(SKIN_MANIFEST) => {}
+            _ => report(&format!(
+                "mods/{}: not a file the game uses, ignoring it",
+                path
+            )),
+        }
+    }
+    commands.insert_resource(images);
+    commands.insert_resource(fonts);
+    commands.insert_resource(sounds);
+}
+
+fn apply_overrides<A: ModAsset>(
+    asset_server: Res<AssetServer>,
+    mut pending: ResMut<PendingOverrides<A>>,
+    mut assets: ResMut<Assets<A>>,
+    mut applied: ResMut<AppliedOverrides>,
+) {
+    pending.0.retain(|over| {
+        match (
+            asset_server.load_state(&over.modded),
+            asset_server.load_state(&over.embedded),
+        ) {
+            (LoadState::Failed(err), _) => {
+                report(&format!(
+                    "mods/{}: failed to load ({}), using the built-in one",
+                    over.path, err
+                ));
+                false
+            }
+            // nothing built in to replace, like the sheet of a new skin
+            (_, LoadState::Failed(_)) => false,
+            (LoadState::Loaded, LoadState::Loaded) => {
+                let (Some(modded), Some(embedded)) =
+                    (assets.get(&over.modded), assets.get(&over.embedded))
+                else {
+                    return true;
+                };
+                if let Err(reason) = A::validate(modded, embedded) {
+                    report(&format!(
+                        "mods/{}: {}, using the built-in one",
+                        over.path, reason
+                    ));
+                    return false;
+                }
+                if let Some(asset) = assets.remove(&over.modded) {
+                    assets.insert(&over.embedded, asset);
+                    applied.0.push(over.embedded.clone().untyped());
+                    println!("Using mods/{}", over.path);
+                }
+                false
+            }
+            _ => true,
+        }
+    });
+}
+
+// A skin folder in the mods adds a skin, or replaces the built-in one with the same name
+fn load_mod_skins(
+    mut registry: ResMut<SkinRegistry>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    let skins_dir = mods_dir().join(SKINS_DIR);
+    let Ok(entries) = fs::read_dir(&skins_dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let manifest_path = entry.path().join(SKIN_MANIFEST);
+        if !manifest_path.is_file() {
+            continue;
+        }
+        let shown = format!(
+            "mods/{}/{}/{}",
+            SKINS_DIR,
+            entry.file_name().to_string_lossy(),
+            SKIN_MANIFEST
+        );
+        let manifest = match fs::read_to_string(&manifest_path)
+            .map_err(|err| err.to_string())
+            .and_then(|contents| {
+                ron::from_str::<SkinManifest>(&contents).map_err(|err| err.to_string())
+            })
+            .and_then(|manifest| manifest.validate().map(|_| manifest))
+        {
+            Ok(manifest) => manifest,
+            Err(err) => {
+                report(&format!("{}: {}, using the built-in skins", shown, err));
+                continue;
+            }
+        };
+        let (width, height) = manifest.frame_size;
+        let Some(expected) = u32::try_from(manifest.frames)
+            .ok()
+            .and_then(|frames| width.checked_mul(frames))
+            .map(|sheet_width| UVec2::new(sheet_width, height))
+        else {
+            report(&format!(
+                "{}: {} frames of {}px are too wide for a sheet, using the built-in skins",
+                shown, manifest.frames, width
+            ));
+            continue;
+        };
+        let sheet_path = mods_dir().join(&manifest.sheet);
+        let Some(size) = png_size(&sheet_path) else {
+            report(&format!(
+                "{}: sheet mods/{} is missing or not a PNG, using the built-in skins",
+                shown, manifest.sheet
+            ));
+            continue;
+        };
+        if size != expected {
+            report(&format!(
+                "{}: sheet is {}x{} but {} frames of {}x{} need {}x{}, using the built-in skins",
+                shown, size.x, size.y, manifest.frames, width, height, expected.x, expected.y
+            ));
+            continue;
+        }
+        println!("Loaded skin {} from {}", manifest.name, shown);
+        registry.register(
+            manifest,
+            MODS_SOURCE,
+            &asset_server,
+            &mut texture_atlas_layouts,
+        );
+    }
+}
+
+// Reads user assets from the mods folder, has to be added before the AssetPlugin
+pub struct ModsPlugin;
+
+impl Plugin for ModsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_asset_source(
+            MODS_SOURCE,
+            AssetSource::build().with_reader(AssetSource::get_default_reader(
+                mods_dir().to_string_lossy().into_owned(),
+            )),
+        )
+        .init_resource::<AppliedOverrides>()
+        .add_systems(PreStartup, scan_mods)
+        .add_systems(
+            PreStartup,
+            load_mod_skins.after(load_skins).before(select_skin),
+        )
+        .add_systems(
+            Update,
+            (
+                apply_overrides::<Image>.run_if(resource_exists::<PendingOverrides<Image>>),
+                apply_overrides::<Font>.run_if(resource_exists::<PendingOverrides<Font>>),
+                apply_overrides::<AudioSource>
+                    .run_if(resource_exists::<PendingOverrides<AudioSource>>),
+            ),
+        );
+    }
+}
@@ -24,6 +24,8 @@ SOFTWARE.
 
 use bevy::prelude::*;
 
+use super::Eliminated;
+
 #[derive(Component)]
 pub struct AnimationIndices {
     pub first: usize,
@@ -35,7 +37,11 @@ pub struct AnimationTimer(pub Timer);
 
 pub fn animate_sprite(
     time: Res<Time>,
-    mut query: Query<(&AnimationIndices, &mut AnimationTimer, &mut TextureAtlas)>,
+    // a crashed bird stops flapping its wings
+    mut query: Query<
+        (&AnimationIndices, &mut AnimationTimer, &mut TextureAtlas),
+        Without<Eliminated>,
+    >,
 ) {
     for (indices, mut timer, mut atlas) in &mut query {
         timer.tick(time.delta());
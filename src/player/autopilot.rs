@@ -0,0 +1,177 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{cli::Args, timestep::GameplaySet, world::pipes::Pipe, FlappybirdState, Playfield};
+
+use super::{
+    controller::{FlapIntent, InputSource},
+    neural::{load_genome, NeuralPilot},
+    Player,
+};
+
+// Half the width of a pipe plus half the bird, past this a pipe can no longer be hit
+pub const PIPE_CLEARANCE: f32 = 15. + 21.;
+
+// Centers of the gaps the bird still has to fly through, nearest first
+pub fn gaps_ahead<'a>(bird: Vec2, pipes: impl Iterator<Item = &'a Transform>) -> Vec<Vec2> {
+    let mut gaps: Vec<Vec2> = pipes
+        .map(|pipe| pipe.translation.truncate())
+        .filter(|gap| gap.x + PIPE_CLEARANCE > bird.x)
+        .collect();
+    gaps.sort_by(|a, b| a.x.total_cmp(&b.x));
+    gaps
+}
+
+// Everything a pilot gets to look at before deciding on a flap
+#[derive(Debug, Clone)]
+pub struct PilotView {
+    pub bird: Vec2,
+    pub velocity: Vec2,
+    // centers of the pipe gaps still ahead of the bird, nearest first
+    pub gaps: Vec<Vec2>,
+    pub playfield: Playfield,
+}
+
+pub trait Pilot: Send + Sync {
+    fn name(&self) -> &str;
+    fn should_flap(&mut self, view: &PilotView) -> bool;
+}
+
+// Stays a little under the center of the next gap and lets gravity do the rest
+#[derive(Debug, Clone)]
+pub struct GapFollower {
+    // how far below the gap center the bird aims
+    pub offset: f32,
+    // no flapping while the bird still climbs faster than this
+    pub max_rise: f32,
+}
+
+impl Default for GapFollower {
+    fn default() -> Self {
+        Self {
+            offset: 25.,
+            max_rise: 100.,
+        }
+    }
+}
+
+impl Pilot for GapFollower {
+    fn name(&self) -> &str {
+        "gap follower"
+    }
+
+    fn should_flap(&mut self, view: &PilotView) -> bool {
+        let target = view
+            .gaps
+            .first()
+            .map_or(view.playfield.height / 2., |gap| gap.y)
+            - self.offset;
+        view.bird.y < target && view.velocity.y < self.max_rise
+    }
+}
+
+// The pilot flying the bird whenever the input source is the autopilot
+#[derive(Resource, Deref, DerefMut)]
+pub struct Autopilot(pub Box<dyn Pilot>);
+
+impl Default for Autopilot {
+    fn default() -> Self {
+        Self(Box::new(GapFollower::default()))
+    }
+}
+
+fn autopilot_input(
+    mut autopilot: ResMut<Autopilot>,
+    state: Res<State<FlappybirdState>>,
+    playfield: Res<Playfield>,
+    mut player_query: Query<(&Transform, &Velocity, &mut FlapIntent), With<Player>>,
+    pipe_query: Query<&Transform, With<Pipe>>,
+) {
+    let Ok((transform, velocity, mut intent)) = player_query.get_single_mut() else {
+        return;
+    };
+    // the run only starts on the first flap
+    if state.get() == &FlappybirdState::TapTap {
+        intent.0 = true;
+        return;
+    }
+
+    let bird = transform.translation.truncate();
+    let view = PilotView {
+        bird,
+        velocity: velocity.linvel,
+        gaps: gaps_ahead(bird, pipe_query.iter()),
+        playfield: *playfield,
+    };
+    if autopilot.should_flap(&view) {
+        intent.0 = true;
+    }
+}
+
+fn apply_autopilot_flag(args: Res<Args>, mut input_source: ResMut<InputSource>) {
+    if !args.autopilot {
+        return;
+    }
+    if args.replay.is_some() {
+        eprintln!("Ignoring --autopilot, the replay is flying the bird");
+        return;
+    }
+    *input_source = InputSource::Autopilot;
+}
+
+// A genome bred in evolve mode replaces the built-in pilot
+fn load_genome_pilot(args: Res<Args>, mut autopilot: ResMut<Autopilot>) {
+    let Some(path) = &args.genome else {
+        return;
+    };
+    match load_genome(path) {
+        Some((genome, _)) => {
+            println!("Flying with the genome from {}", path.display());
+            *autopilot = Autopilot(Box::new(NeuralPilot { genome }));
+        }
+        None => eprintln!("Failed to load genome {}", path.display()),
+    }
+}
+
+pub struct AutopilotPlugin;
+
+impl Plugin for AutopilotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Autopilot>()
+            .add_systems(Startup, (apply_autopilot_flag, load_genome_pilot))
+            .add_systems(
+                FixedUpdate,
+                autopilot_input
+                    .in_set(GameplaySet::Input)
+                    .run_if(resource_equals(InputSource::Autopilot))
+                    .run_if(
+                        in_state(FlappybirdState::TapTap)
+                            .or_else(in_state(FlappybirdState::InGame)),
+                    ),
+            );
+    }
+}
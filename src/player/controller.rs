@@ -22,18 +22,72 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
 
-use bevy::{prelude::*, window::PrimaryWindow};
-use bevy_kira_audio::{Audio, AudioControl};
+use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
-use crate::{ui::taptap::TapTapUI, FlappybirdState};
+use crate::{
+    modes::GameMode,
+    sfx::{PlaySfx, Sfx},
+    ui::taptap::TapTapUI,
+    FlappybirdState, Playfield,
+};
 
-use super::Player;
+use super::{ghost::Ghost, Eliminated, Player, Seat};
 
 pub const PLAYER_SPEED: f32 = 500.; // M/S
+pub const FLAP_IMPULSE: f32 = 4000.;
+// Every bird's body, the colliders have no density so this is all of it
+pub const BIRD_MASS: f32 = 10.;
+
+// How much a flap adds to the bird's climb. Rapier scales impulses and speeds by the same
+// pixels per meter, so the impulse over the mass is already in pixels
+pub const FLAP_SPEED: f32 = FLAP_IMPULSE / BIRD_MASS;
+
+// Set by whatever is flying the bird this frame, consumed by player_movement
+#[derive(Debug, Component, Default)]
+pub struct FlapIntent(pub bool);
+
+// Who gets to set the FlapIntent of the player
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputSource {
+    #[default]
+    Human,
+    Replay,
+    Autopilot,
+    // set from outside the app, like the gym environment
+    Agent,
+}
+
+#[derive(Event, Debug)]
+pub struct Flapped {
+    pub bird: Entity,
+}
+
+// In versus player one keeps Space and player two gets Enter and the mouse
+pub fn human_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mode: Res<GameMode>,
+    mut player_query: Query<(&Seat, &mut FlapIntent), With<Player>>,
+) {
+    let click = mouse_input.just_pressed(MouseButton::Left);
+    let pressed = if *mode == GameMode::Versus {
+        [
+            keyboard_input.just_pressed(KeyCode::Space),
+            keyboard_input.just_pressed(KeyCode::Enter) || click,
+        ]
+    } else {
+        [keyboard_input.just_pressed(KeyCode::Space) || click, false]
+    };
+    for (seat, mut intent) in player_query.iter_mut() {
+        if pressed.get(seat.0).copied().unwrap_or(false) {
+            intent.0 = true;
+        }
+    }
+}
 
 pub fn player_velocity_limiter(mut player_query: Query<&mut Velocity, With<Player>>) {
-    if let Ok(mut v) = player_query.get_single_mut() {
+    for mut v in player_query.iter_mut() {
         if v.linvel.y > PLAYER_SPEED {
             v.linvel.y = PLAYER_SPEED;
         }
@@ -41,51 +95,57 @@ pub fn player_velocity_limiter(mut player_query: Query<&mut Velocity, With<Playe
     }
 }
 
-const UPWARD_ROTATION_LIMIT: f32 = 25.; // Adjust for faster upward rotation
+pub const UPWARD_ROTATION_LIMIT: f32 = 25.; // Adjust for faster upward rotation
 const DOWNWARD_ROTATION_LIMIT: f32 = -90.; // Adjust for slower downward rotation
 
 pub fn player_movement(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    audio: Res<Audio>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mouse_input: Res<ButtonInput<MouseButton>>,
+    mut sfx: EventWriter<PlaySfx>,
+    mut flapped: EventWriter<Flapped>,
     mut player_query: Query<
-        (&mut Transform, &mut ExternalImpulse, &mut Velocity, Entity),
-        With<Player>,
+        (
+            &mut Transform,
+            &mut ExternalImpulse,
+            &mut Velocity,
+            &mut FlapIntent,
+            Entity,
+        ),
+        (With<Player>, Without<Eliminated>),
     >,
-    window_query: Query<&Window, With<PrimaryWindow>>,
+    playfield: Res<Playfield>,
     taptap_query: Query<Entity, With<TapTapUI>>,
     mut mutable_state: ResMut<NextState<FlappybirdState>>,
     current_state: Res<State<FlappybirdState>>,
 ) {
-    if let Ok((mut transform, mut impulse, mut velocity, entity)) = player_query.get_single_mut() {
-        if (keyboard_input.just_pressed(KeyCode::Space)
-            || mouse_input.just_pressed(MouseButton::Left))
-            && transform.translation.y < window_query.get_single().unwrap().height()
-        {
-            if current_state.get() == &FlappybirdState::TapTap {
+    let mut started = current_state.get() != &FlappybirdState::TapTap;
+    let mut birds = Vec::new();
+    for (mut transform, mut impulse, mut velocity, mut intent, entity) in player_query.iter_mut() {
+        birds.push(entity);
+        let wants_flap = std::mem::take(&mut intent.0);
+        if wants_flap && transform.translation.y < playfield.height {
+            if !started {
                 for entity in taptap_query.iter() {
                     commands.entity(entity).despawn_recursive();
                 }
                 mutable_state.set(FlappybirdState::InGame);
-                commands
-                    .get_entity(entity)
-                    .unwrap()
-                    .insert(RigidBody::Dynamic);
+                started = true;
                 //state.set(FlappybirdState::InGame);
             }
             println!("Space pressed");
-            audio.play(
-                asset_server
-                    .get_handle("embedded://audio/sfx_wing.ogg")
-                    .unwrap_or(asset_server.load("embedded://audio/sfx_wing.ogg")),
-            );
+            sfx.send(PlaySfx(Sfx::Wing));
             if velocity.linvel.y < 0. {
                 velocity.linvel.y = 0.;
             }
-            impulse.impulse = Vec2::new(0., 4000.);
+            impulse.impulse = Vec2::new(0., FLAP_IMPULSE);
             transform.rotation = Quat::from_rotation_z(f32::to_radians(UPWARD_ROTATION_LIMIT));
+            flapped.send(Flapped { bird: entity });
+        }
+    }
+
+    // the first flap starts the run for every bird, not just the one that flapped
+    if started && current_state.get() == &FlappybirdState::TapTap {
+        for entity in birds {
+            commands.entity(entity).insert(RigidBody::Dynamic);
         }
     }
 }
@@ -99,7 +159,10 @@ pub fn player_movement_restrictions(mut query: Query<&mut Velocity, With<Player>
 const ROTATION_INTERPOLATION_SPEED: f32 = 2.; // Base speed of rotation interpolation
 pub fn player_rotation(
     time: Res<Time>,
-    mut query: Query<(&Velocity, &mut Transform), With<Player>>,
+    mut query: Query<
+        (&Velocity, &mut Transform),
+        (Or<(With<Player>, With<Ghost>)>, Without<Eliminated>),
+    >,
 ) {
     for (velocity, mut transform) in &mut query {
         let delta_time = time.delta_seconds();
@@ -0,0 +1,238 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+
+use crate::{
+    sfx::{PlaySfx, Sfx},
+    timestep::GameplaySet,
+    world::ground::Ground,
+    FlappybirdState, Playfield,
+};
+
+use super::{Eliminated, Player};
+
+const DIE_CRY_DELAY: f32 = 0.3; // seconds between the hit and the die sound
+const NOSE_DIVE_SPEED: f32 = 10.; // radians per second
+const FLASH_TIME: f32 = 0.2;
+// in case a bird never reports touching the ground
+const MAX_FALL_TIME: f32 = 3.;
+const SHAKE_TIME: f32 = 0.3;
+const SHAKE_STRENGTH: f32 = 12.; // pixels
+
+// A bird that crashed in the air screams on its way down
+#[derive(Debug, Component)]
+pub struct DeathCry(Timer);
+
+impl Default for DeathCry {
+    fn default() -> Self {
+        Self(Timer::from_seconds(DIE_CRY_DELAY, TimerMode::Once))
+    }
+}
+
+#[derive(Resource)]
+struct FallTimer(Timer);
+
+#[derive(Component)]
+struct Flash(Timer);
+
+#[derive(Resource)]
+struct CameraShake(Timer);
+
+fn death_cry(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut sfx: EventWriter<PlaySfx>,
+    mut bird_query: Query<(Entity, &mut DeathCry)>,
+) {
+    for (entity, mut cry) in bird_query.iter_mut() {
+        if cry.0.tick(time.delta()).just_finished() {
+            sfx.send(PlaySfx(Sfx::Die));
+            commands.entity(entity).remove::<DeathCry>();
+        }
+    }
+}
+
+fn nose_dive(time: Res<Time>, mut bird_query: Query<&mut Transform, With<Eliminated>>) {
+    let target = -std::f32::consts::FRAC_PI_2;
+    for mut transform in bird_query.iter_mut() {
+        let current = transform.rotation.to_euler(EulerRot::XYZ).2;
+        let step = NOSE_DIVE_SPEED * time.delta_seconds();
+        transform.rotation = Quat::from_rotation_z((current - step).max(target));
+    }
+}
+
+fn land_on_ground(
+    mut collision_events: EventReader<CollisionEvent>,
+    mut bird_query: Query<&mut Eliminated>,
+    ground_query: Query<(), With<Ground>>,
+) {
+    for event in collision_events.read() {
+        let CollisionEvent::Started(entity1, entity2, _) = event else {
+            continue;
+        };
+        for (bird, other) in [(*entity1, *entity2), (*entity2, *entity1)] {
+            if ground_query.get(other).is_err() {
+                continue;
+            }
+            if let Ok(mut eliminated) = bird_query.get_mut(bird) {
+                eliminated.landed = true;
+            }
+        }
+    }
+}
+
+fn start_falling(mut fall_timer: ResMut<FallTimer>) {
+    fall_timer.0.reset();
+}
+
+fn finish_dying(
+    time: Res<Time>,
+    mut fall_timer: ResMut<FallTimer>,
+    bird_query: Query<&Eliminated, With<Player>>,
+    mut next_state: ResMut<NextState<FlappybirdState>>,
+) {
+    fall_timer.0.tick(time.delta());
+    if fall_timer.0.finished() || bird_query.iter().all(|eliminated| eliminated.landed) {
+        next_state.set(FlappybirdState::GameOver);
+    }
+}
+
+// The bird's crash, down to it hitting the ground, simulated with or without a window
+pub struct DeathPlugin;
+
+impl Plugin for DeathPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(FallTimer(Timer::from_seconds(
+            MAX_FALL_TIME,
+            TimerMode::Once,
+        )))
+        .add_systems(OnEnter(FlappybirdState::Dying), start_falling)
+        // the cry can outlast the fall when the bird crashes close to the ground
+        .add_systems(FixedUpdate, death_cry.in_set(GameplaySet::Simulation))
+        .add_systems(
+            FixedUpdate,
+            nose_dive.in_set(GameplaySet::Simulation).run_if(
+                in_state(FlappybirdState::InGame).or_else(in_state(FlappybirdState::Dying)),
+            ),
+        )
+        .add_systems(
+            FixedUpdate,
+            (
+                land_on_ground,
+                finish_dying.run_if(in_state(FlappybirdState::Dying)),
+            )
+                .chain()
+                .in_set(GameplaySet::Collisions)
+                .run_if(
+                    in_state(FlappybirdState::InGame).or_else(in_state(FlappybirdState::Dying)),
+                ),
+        );
+    }
+}
+
+fn start_death_effects(mut commands: Commands, crashed_query: Query<(), Added<Eliminated>>) {
+    if crashed_query.is_empty() {
+        return;
+    }
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            background_color: Color::WHITE.into(),
+            z_index: ZIndex::Global(100),
+            ..default()
+        },
+        Flash(Timer::from_seconds(FLASH_TIME, TimerMode::Once)),
+    ));
+    commands.insert_resource(CameraShake(Timer::from_seconds(
+        SHAKE_TIME,
+        TimerMode::Once,
+    )));
+}
+
+fn fade_flash(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut flash_query: Query<(Entity, &mut Flash, &mut BackgroundColor)>,
+) {
+    for (entity, mut flash, mut background) in flash_query.iter_mut() {
+        flash.0.tick(time.delta());
+        background.0.set_alpha(flash.0.fraction_remaining());
+        if flash.0.finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+// Shaking is only for show, so it uses its own randomness and leaves the course alone
+fn shake_camera(
+    mut commands: Commands,
+    time: Res<Time>,
+    playfield: Res<Playfield>,
+    mut shake: ResMut<CameraShake>,
+    mut camera_query: Query<&mut Transform, With<Camera>>,
+) {
+    shake.0.tick(time.delta());
+    let strength = SHAKE_STRENGTH * shake.0.fraction_remaining();
+    let mut rng = rand::thread_rng();
+    for mut transform in camera_query.iter_mut() {
+        let offset = if shake.0.finished() {
+            Vec2::ZERO
+        } else {
+            Vec2::new(
+                rng.gen_range(-strength..=strength),
+                rng.gen_range(-strength..=strength),
+            )
+        };
+        transform.translation.x = playfield.width / 2. + offset.x;
+        transform.translation.y = playfield.height / 2. + offset.y;
+    }
+    if shake.0.finished() {
+        commands.remove_resource::<CameraShake>();
+    }
+}
+
+// The flash and the shake, only added when there is a window to show them in
+pub struct DeathEffectsPlugin;
+
+impl Plugin for DeathEffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                start_death_effects,
+                fade_flash,
+                shake_camera.run_if(resource_exists::<CameraShake>),
+            )
+                .chain(),
+        );
+    }
+}
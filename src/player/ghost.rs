@@ -0,0 +1,370 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    modes::GameMode,
+    replay::{
+        advance_run_tick, best_replay_for_seed, record_flaps, run_started, Replay, RunTick, Tuning,
+    },
+    timestep::{GameplaySet, RenderInterpolation},
+    world::{difficulty::Difficulty, seed_world_rng, WorldRng},
+    FlappybirdState,
+};
+
+use super::{
+    controller::{InputSource, BIRD_MASS, FLAP_IMPULSE, PLAYER_SPEED, UPWARD_ROTATION_LIMIT},
+    skin::{ActiveSkin, Skin, SkinRegistry},
+    Player,
+};
+
+const GHOST_ALPHA: f32 = 0.4;
+const GHOST_FADE: f32 = 0.5; // seconds
+
+// Re-enacts the best saved run on this seed from its flap log
+#[derive(Component)]
+pub struct Ghost {
+    flaps: Vec<u32>,
+    next: usize,
+    end: u32,
+    fade: Timer,
+}
+
+impl Ghost {
+    // Whether the recorded run flapped by `tick`, each flap is given out once
+    fn flap_due(&mut self, tick: u32) -> bool {
+        let due = tick < self.end && self.flaps.get(self.next).is_some_and(|&flap| flap <= tick);
+        if due {
+            self.next += 1;
+        }
+        due
+    }
+}
+
+fn spawn_ghost(
+    mut commands: Commands,
+    input_source: Res<InputSource>,
+    mode: Res<GameMode>,
+    rng: Res<WorldRng>,
+    difficulty: Res<Difficulty>,
+    ghost_query: Query<Entity, With<Ghost>>,
+    player_query: Query<&Transform, With<Player>>,
+    registry: Res<SkinRegistry>,
+    active_skin: Res<ActiveSkin>,
+) {
+    for entity in ghost_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    if *input_source != InputSource::Human || matches!(*mode, GameMode::Versus | GameMode::Level) {
+        return;
+    }
+    let Ok(transform) = player_query.get_single() else {
+        return;
+    };
+    let Some(replay) = best_replay_for_seed(
+        rng.seed,
+        Tuning::current(difficulty.curve.start().speed),
+        difficulty.preset,
+    ) else {
+        return;
+    };
+    println!(
+        "Racing your best run on seed {} ({} points)",
+        replay.seed, replay.score
+    );
+    commands.spawn(ghost(replay, *transform, registry.get(active_skin.0)));
+}
+
+// A see-through bird that flies `replay`, starting where the player is
+fn ghost(replay: Replay, transform: Transform, skin: &Skin) -> impl Bundle {
+    let mut ghost_transform = transform;
+    ghost_transform.translation.z -= 0.1; // behind the player
+    let animation_indices = skin.animation_indices();
+
+    (
+        SpriteBundle {
+            transform: ghost_transform,
+            texture: skin.texture.clone(),
+            sprite: Sprite {
+                color: Color::srgba(1., 1., 1., GHOST_ALPHA),
+                ..default()
+            },
+            ..Default::default()
+        },
+        TextureAtlas {
+            layout: skin.layout.clone(),
+            index: animation_indices.first,
+        },
+        animation_indices,
+        skin.animation_timer(),
+        Ghost {
+            flaps: replay.flaps,
+            next: 0,
+            end: replay.ticks,
+            fade: Timer::from_seconds(GHOST_FADE, TimerMode::Once),
+        },
+        // same body as the player so it flies the same, but it touches nothing
+        RigidBody::Fixed,
+        ExternalImpulse::default(),
+        ColliderMassProperties::Density(0.),
+        AdditionalMassProperties::Mass(BIRD_MASS),
+        Velocity::default(),
+        skin.manifest.collider.collider(),
+        CollisionGroups::new(Group::NONE, Group::NONE),
+        RenderInterpolation::default(),
+    )
+}
+
+// The same steps player_movement and the limiters take, fed from the flap log. The first
+// flap is on the tick the player's first flap is, and lets the ghost go like it does the bird
+fn fly_ghost(
+    mut commands: Commands,
+    tick: Res<RunTick>,
+    mut ghost_query: Query<(
+        Entity,
+        &mut Ghost,
+        &mut Transform,
+        &mut Velocity,
+        &mut ExternalImpulse,
+    )>,
+) {
+    for (entity, mut ghost, mut transform, mut velocity, mut impulse) in ghost_query.iter_mut() {
+        if ghost.flap_due(tick.0) {
+            if ghost.next == 1 {
+                commands.entity(entity).insert(RigidBody::Dynamic);
+            }
+            if velocity.linvel.y < 0. {
+                velocity.linvel.y = 0.;
+            }
+            impulse.impulse = Vec2::new(0., FLAP_IMPULSE);
+            transform.rotation = Quat::from_rotation_z(f32::to_radians(UPWARD_ROTATION_LIMIT));
+        }
+        velocity.linvel.x = 0.;
+        velocity.linvel.y = velocity.linvel.y.min(PLAYER_SPEED);
+    }
+}
+
+// Fades out once the recorded run is over, or when the player's is
+fn fade_ghost(
+    mut commands: Commands,
+    time: Res<Time>,
+    tick: Res<RunTick>,
+    state: Res<State<FlappybirdState>>,
+    mut ghost_query: Query<(Entity, &mut Ghost, &mut Sprite)>,
+) {
+    for (entity, mut ghost, mut sprite) in ghost_query.iter_mut() {
+        let crashed = matches!(
+            state.get(),
+            FlappybirdState::Dying | FlappybirdState::GameOver
+        );
+        if tick.0 < ghost.end && !crashed {
+            continue;
+        }
+        ghost.fade.tick(time.delta());
+        sprite
+            .color
+            .set_alpha(GHOST_ALPHA * ghost.fade.fraction_remaining());
+        if ghost.fade.finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn despawn_ghost(mut commands: Commands, ghost_query: Query<Entity, With<Ghost>>) {
+    for entity in ghost_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(FlappybirdState::TapTap),
+            spawn_ghost.after(seed_world_rng),
+        )
+        .add_systems(OnEnter(FlappybirdState::MainTitle), despawn_ghost)
+        .add_systems(
+            FixedUpdate,
+            fly_ghost
+                .after(record_flaps)
+                .before(advance_run_tick)
+                .in_set(GameplaySet::Simulation)
+                .run_if(run_started),
+        )
+        .add_systems(Update, fade_ghost);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cli::Args,
+        headless::headless_app,
+        player::controller::FlapIntent,
+        replay::{ReplayPlayback, ReplayRecorder},
+        Playfield,
+    };
+
+    const SEED: u64 = 7;
+    // more than any run of the test bird should last
+    const MAX_TICKS: usize = 60 * 60;
+
+    fn set_state(app: &mut App, state: FlappybirdState) {
+        app.world_mut()
+            .resource_mut::<NextState<FlappybirdState>>()
+            .set(state);
+        app.update();
+    }
+
+    // A headless game on the test course, waiting in TapTap for the first flap
+    fn course(input_source: InputSource) -> App {
+        let mut app = headless_app(Args {
+            seed: Some(SEED),
+            ..default()
+        });
+        app.insert_resource(input_source);
+        app.update();
+        // going through game over is what puts the bird and pipes in place
+        set_state(&mut app, FlappybirdState::GameOver);
+        set_state(&mut app, FlappybirdState::TapTap);
+        app
+    }
+
+    fn flying(app: &App) -> bool {
+        matches!(
+            app.world().resource::<State<FlappybirdState>>().get(),
+            FlappybirdState::TapTap | FlappybirdState::InGame
+        )
+    }
+
+    fn finish(app: &mut App) -> u64 {
+        for _ in 0..MAX_TICKS {
+            if app.world().resource::<State<FlappybirdState>>().get() == &FlappybirdState::GameOver
+            {
+                let world = app.world_mut();
+                return world.query::<&Player>().single(world).score;
+            }
+            app.update();
+        }
+        panic!("the run never ended");
+    }
+
+    fn height<F: bevy::ecs::query::QueryFilter>(app: &mut App) -> f32 {
+        let world = app.world_mut();
+        world
+            .query_filtered::<&Transform, F>()
+            .single(world)
+            .translation
+            .y
+    }
+
+    // Flies a run through the game, then plays it back as the bird's input and as a ghost
+    #[test]
+    fn a_recorded_run_replays_to_the_same_path() {
+        let mut recording = course(InputSource::Agent);
+        let middle = recording.world().resource::<Playfield>().height / 2.;
+        let mut path = Vec::new();
+        while flying(&recording) {
+            assert!(path.len() < MAX_TICKS, "the test bird never crashed");
+            // keeps to the middle of the screen until a pipe gets in the way
+            if path.is_empty() || height::<With<Player>>(&mut recording) < middle {
+                let world = recording.world_mut();
+                world
+                    .query_filtered::<&mut FlapIntent, With<Player>>()
+                    .single_mut(world)
+                    .0 = true;
+            }
+            recording.update();
+            path.push(height::<With<Player>>(&mut recording));
+        }
+        let score = finish(&mut recording);
+        let difficulty = recording.world().resource::<Difficulty>();
+        let replay = Replay {
+            seed: SEED,
+            tuning: Tuning::current(difficulty.curve.start().speed),
+            difficulty: difficulty.preset,
+            score,
+            ticks: recording.world().resource::<RunTick>().0,
+            flaps: recording.world().resource::<ReplayRecorder>().flaps.clone(),
+        };
+        assert!(replay.flaps.len() > 1);
+
+        let mut playback = course(InputSource::Agent);
+        let world = playback.world_mut();
+        let transform = *world
+            .query_filtered::<&Transform, With<Player>>()
+            .single(world);
+        let ticks = replay.ticks as usize;
+        let bundle = ghost(
+            replay.clone(),
+            transform,
+            world.resource::<SkinRegistry>().get(0),
+        );
+        world.spawn(bundle);
+        world.insert_resource(InputSource::Replay);
+        world.insert_resource(ReplayPlayback {
+            replay,
+            next: 0,
+            started: true,
+        });
+        for (tick, &y) in path.iter().enumerate() {
+            assert!(flying(&playback), "crashed early on tick {tick}");
+            playback.update();
+            assert_eq!(
+                height::<With<Player>>(&mut playback),
+                y,
+                "bird on tick {tick}"
+            );
+            // the ghost flies through the pipe the bird runs into, and the pipe's contact
+            // can nudge the bird a tick before the crash counts
+            if tick + 3 < ticks {
+                assert_eq!(
+                    height::<With<Ghost>>(&mut playback),
+                    y,
+                    "ghost on tick {tick}"
+                );
+            }
+        }
+        assert!(!flying(&playback), "outlived the recorded run");
+        assert_eq!(finish(&mut playback), score);
+    }
+
+    #[test]
+    fn the_first_flap_is_on_the_first_tick() {
+        let mut ghost = Ghost {
+            flaps: vec![0, 10],
+            next: 0,
+            end: 20,
+            fade: Timer::from_seconds(GHOST_FADE, TimerMode::Once),
+        };
+        assert!(ghost.flap_due(0));
+        assert!(!ghost.flap_due(1));
+        assert!(ghost.flap_due(10));
+    }
+}
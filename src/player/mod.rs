@@ -22,133 +22,265 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
 
-use std::time::Duration;
-
-use animation::{animate_sprite, AnimationIndices, AnimationTimer};
-use bevy::{prelude::*, window::PrimaryWindow};
-use bevy_kira_audio::{Audio, AudioControl};
+use animation::animate_sprite;
+use bevy::{input::InputSystem, prelude::*};
 use bevy_rapier2d::prelude::*;
 
 use crate::{
-    world::pipes::{self, Pipe, PipeSegment},
-    FlappybirdState,
+    replay::RunTick,
+    sfx::{PlaySfx, Sfx},
+    timestep::{GameplaySet, RenderInterpolation},
+    world::{
+        ground::Ground,
+        pipes::{Pipe, PIPE_GROUP},
+    },
+    FlappybirdState, Playfield,
 };
 
 pub mod animation;
+pub mod autopilot;
 pub mod controller;
+pub mod death;
+pub mod ghost;
+pub mod neural;
+pub mod skin;
 
 #[derive(Debug, Component, Default)]
 pub struct Player {
     pub name: String,
     pub score: u64,
-    pub last_score_time: f32,
+}
+
+// Which player a bird (and its score readout) belongs to, 0 is player one
+#[derive(Debug, Component, Clone, Copy, PartialEq, Eq)]
+pub struct Seat(pub usize);
+
+// A bird that has crashed, it no longer flaps or scores and drops to the ground
+#[derive(Debug, Component)]
+pub struct Eliminated {
+    pub tick: u32,
+    pub landed: bool,
+}
+
+pub const BIRD_GROUP: Group = Group::GROUP_1;
+
+// Birds are drawn, and collide, at three times the sprite's size
+pub const BIRD_SCALE: f32 = 3.;
+
+// The built-in bird's body, and the biggest one a skin may give it since the course
+// generator lays out its gaps for it
+pub const BIRD_BODY: SkinCollider = SkinCollider {
+    half_length: 1.,
+    radius: 6.,
+};
+
+// Birds fly through each other, only pipes and the ground are deadly
+pub fn bird_collision_groups() -> CollisionGroups {
+    CollisionGroups::new(BIRD_GROUP, Group::ALL ^ BIRD_GROUP)
 }
 
 pub fn spawn_player(
     mut commands: Commands,
-    window_query: Query<&Window, With<PrimaryWindow>>,
-    asset_server: Res<AssetServer>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    playfield: Res<Playfield>,
+    registry: Res<SkinRegistry>,
+    active_skin: Res<ActiveSkin>,
 ) {
-    let primary_window = window_query.get_single().unwrap();
-
-    // Load texture for the bird, can be randomized later
-    let texture = asset_server.load("embedded://sprites/birds/yellow/bird-sheet.png");
-    // the sprite sheet has 4 sprites arranged in a row, and they are all 17px x 12px
-    let layout = TextureAtlasLayout::from_grid(UVec2::new(17, 12), 4, 1, None, None);
-    let texture_atlas_layout = texture_atlas_layouts.add(layout);
-    let animation_indices = AnimationIndices { first: 0, last: 3 };
+    let skin = registry.get(active_skin.0);
+    let animation_indices = skin.animation_indices();
 
-    let mut bird_transform = Transform::from_xyz(
-        primary_window.width() / 2.,
-        primary_window.height() / 2. + 70.,
-        2.,
-    );
+    let mut bird_transform =
+        Transform::from_xyz(playfield.width / 2., playfield.height / 2. + 70., 2.);
 
-    bird_transform.scale = Vec3::splat(3.);
+    bird_transform.scale = Vec3::splat(BIRD_SCALE);
 
     commands.spawn((
         SpriteBundle {
             transform: bird_transform,
-            texture: texture.clone(),
+            texture: skin.texture.clone(),
             ..Default::default()
         },
         TextureAtlas {
-            layout: texture_atlas_layout.clone(),
+            layout: skin.layout.clone(),
             index: animation_indices.first,
         },
         Player {
             name: "helpdesk".to_string(),
             ..Default::default()
         },
+        Seat(0),
         animation_indices,
         RigidBody::Fixed,
         ExternalImpulse::default(),
         ColliderMassProperties::Density(0.),
-        AdditionalMassProperties::Mass(10.0),
+        AdditionalMassProperties::Mass(BIRD_MASS),
         Velocity::default(),
-        Collider::capsule_x(1., 6.),
-        CollisionGroups::default(),
-        AnimationTimer(Timer::new(Duration::from_millis(100), TimerMode::Repeating)),
+        skin.manifest.collider.collider(),
+        bird_collision_groups(),
+        skin.animation_timer(),
+        FlapIntent::default(),
+        RenderInterpolation::default(),
     ));
 }
 
 fn deadly_touch(
+    mut commands: Commands,
     mut collision_events: EventReader<CollisionEvent>,
-    query: Query<Entity, Without<Pipe>>,
-    audio: Res<Audio>,
-    asset_server: Res<AssetServer>,
-    state: Res<State<FlappybirdState>>,
+    mut bird_query: Query<(Entity, &mut CollisionGroups), (With<Player>, Without<Eliminated>)>,
+    pipe_query: Query<(), With<Pipe>>,
+    ground_query: Query<(), With<Ground>>,
+    mut sfx: EventWriter<PlaySfx>,
+    tick: Res<RunTick>,
     mut next_state: ResMut<NextState<FlappybirdState>>,
-    mut pipe_segemnt_query: Query<&mut CollisionGroups, With<PipeSegment>>,
 ) {
-    if state.get() != &FlappybirdState::InGame {
-        return;
-    }
+    let mut eliminated = Vec::new();
     for event in collision_events.read().into_iter() {
-        match event {
-            CollisionEvent::Started(entity1, entity2, _) => {
-                if query.get(*entity1).is_ok() && query.get(*entity2).is_ok() {
-                    // TODO: only do this to the pipe segments that the player collided with, make the pipes non-colliable on death
-                    for mut collision_groups in pipe_segemnt_query.iter_mut() {
-                        collision_groups.filters = Group::NONE;
-                    }
-                    next_state.set(FlappybirdState::GameOver);
-                    audio.play(
-                        asset_server
-                            .get_handle("embedded://audio/sfx_hit.ogg")
-                            .unwrap_or(asset_server.load("embedded://audio/sfx_hit.ogg")),
-                    );
-                    println!("touched");
-                }
+        let CollisionEvent::Started(entity1, entity2, _) = event else {
+            continue;
+        };
+        for (bird, other) in [(*entity1, *entity2), (*entity2, *entity1)] {
+            // flying through the gap sensor is not a crash
+            if pipe_query.get(other).is_ok() || eliminated.contains(&bird) {
+                continue;
             }
-            CollisionEvent::Stopped(_, _, _) => {}
+            let Ok((entity, mut collision_groups)) = bird_query.get_mut(bird) else {
+                continue;
+            };
+            // the dead bird drops through the pipes, the rest of the flock still hits them
+            collision_groups.filters = Group::ALL ^ BIRD_GROUP ^ PIPE_GROUP;
+            let landed = ground_query.get(other).is_ok();
+            commands.entity(entity).insert(Eliminated {
+                tick: tick.0,
+                landed,
+            });
+            if !landed {
+                commands.entity(entity).insert(DeathCry::default());
+            }
+            eliminated.push(entity);
+            sfx.send(PlaySfx(Sfx::Hit));
+            println!("touched");
         }
     }
+
+    if !eliminated.is_empty()
+        && bird_query
+            .iter()
+            .all(|(entity, _)| eliminated.contains(&entity))
+    {
+        next_state.set(FlappybirdState::Dying);
+    }
+}
+
+// Puts the birds back where the title screen's Play button leaves them
+pub fn reset_player(
+    mut commands: Commands,
+    playfield: Res<Playfield>,
+    registry: Res<SkinRegistry>,
+    active_skin: Res<ActiveSkin>,
+    mut player_query: Query<
+        (
+            Entity,
+            &mut Player,
+            &mut Transform,
+            &mut Velocity,
+            &mut ExternalImpulse,
+            &mut CollisionGroups,
+            &mut Collider,
+        ),
+        With<Seat>,
+    >,
+) {
+    let skin = registry.get(active_skin.0);
+    for (
+        entity,
+        mut player,
+        mut transform,
+        mut velocity,
+        mut impulse,
+        mut collision_groups,
+        mut collider,
+    ) in player_query.iter_mut()
+    {
+        player.score = 0;
+        transform.translation = Vec3::new(playfield.width / 6., playfield.height / 2., 2.);
+        transform.rotation = Quat::IDENTITY;
+        *velocity = Velocity::zero();
+        impulse.impulse = Vec2::ZERO;
+        *collision_groups = bird_collision_groups();
+        *collider = skin.manifest.collider.collider();
+        commands
+            .entity(entity)
+            .insert(RigidBody::Fixed)
+            .remove::<(Eliminated, DeathCry)>();
+    }
 }
 
 use controller::*;
+use death::DeathCry;
+use skin::{ActiveSkin, SkinCollider, SkinPlugin, SkinRegistry};
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_player)
-            .add_systems(Update, animate_sprite)
+        app.add_plugins(SkinPlugin)
+            .init_resource::<InputSource>()
+            .add_event::<Flapped>()
+            .add_systems(Startup, spawn_player)
             .add_systems(
                 Update,
+                animate_sprite.run_if(not(in_state(FlappybirdState::Paused))),
+            )
+            .add_systems(OnExit(FlappybirdState::GameOver), reset_player)
+            .add_systems(
+                OnTransition {
+                    exited: FlappybirdState::InGame,
+                    entered: FlappybirdState::TapTap,
+                },
+                reset_player,
+            )
+            .add_systems(
+                FixedUpdate,
                 (
                     player_movement,
                     player_movement_restrictions,
                     player_velocity_limiter,
                 )
+                    .chain()
+                    .in_set(GameplaySet::Simulation)
                     .run_if(
                         in_state(FlappybirdState::TapTap)
-                            .or_else(in_state(FlappybirdState::InGame)),
+                            .or_else(in_state(FlappybirdState::InGame))
+                            .or_else(in_state(FlappybirdState::Dying)),
                     ),
             )
             .add_systems(
-                Update,
-                (player_rotation, deadly_touch).run_if(in_state(FlappybirdState::InGame)),
+                FixedUpdate,
+                player_rotation
+                    .after(player_velocity_limiter)
+                    .in_set(GameplaySet::Simulation)
+                    .run_if(in_state(FlappybirdState::InGame)),
+            )
+            .add_systems(
+                FixedUpdate,
+                deadly_touch
+                    .in_set(GameplaySet::Collisions)
+                    .run_if(in_state(FlappybirdState::InGame)),
             );
     }
 }
+
+// Keyboard and mouse flapping, only added when there is a window to read them from
+pub struct PlayerInputPlugin;
+
+impl Plugin for PlayerInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            human_input
+                .after(InputSystem)
+                .run_if(resource_equals(InputSource::Human))
+                .run_if(
+                    in_state(FlappybirdState::TapTap).or_else(in_state(FlappybirdState::InGame)),
+                ),
+        );
+    }
+}
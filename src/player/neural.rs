@@ -0,0 +1,144 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::path::Path;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+use super::{
+    autopilot::{Pilot, PilotView},
+    controller::PLAYER_SPEED,
+};
+
+pub const INPUTS: usize = 5;
+pub const HIDDEN: usize = 6;
+// a bias per hidden neuron and one for the output
+pub const GENOME_LENGTH: usize = HIDDEN * (INPUTS + 1) + HIDDEN + 1;
+
+pub const GENOME_FILE: &str = "genome.ron";
+const GENOME_VERSION: u32 = 1;
+
+// The weights of a small feed-forward network, what the genetic algorithm breeds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Genome {
+    pub weights: Vec<f32>,
+}
+
+impl Genome {
+    pub fn random(rng: &mut impl Rng) -> Self {
+        Self {
+            weights: (0..GENOME_LENGTH)
+                .map(|_| rng.gen_range(-1.0..1.0))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct GenomeFile {
+    version: u32,
+    generation: u32,
+    fitness: f32,
+    genome: Genome,
+}
+
+pub fn save_genome(genome: &Genome, generation: u32, fitness: f32) {
+    storage::save(
+        GENOME_FILE,
+        &GenomeFile {
+            version: GENOME_VERSION,
+            generation,
+            fitness,
+            genome: genome.clone(),
+        },
+    );
+}
+
+// The genome and the fitness it was saved with
+pub fn load_genome(path: &Path) -> Option<(Genome, f32)> {
+    let file = storage::load_path::<GenomeFile>(path)?;
+    if file.version != GENOME_VERSION {
+        eprintln!("Ignoring genome with unknown version {}", file.version);
+        return None;
+    }
+    if file.genome.weights.len() != GENOME_LENGTH {
+        eprintln!(
+            "Ignoring genome with {} weights, expected {}",
+            file.genome.weights.len(),
+            GENOME_LENGTH
+        );
+        return None;
+    }
+    Some((file.genome, file.fitness))
+}
+
+// What the network sees, everything scaled to roughly -1..1
+pub fn features(view: &PilotView) -> [f32; INPUTS] {
+    let field = view.playfield;
+    let gap_offset =
+        |index: usize| view.gaps.get(index).map_or(field.height / 2., |gap| gap.y) - view.bird.y;
+    [
+        view.bird.y / field.height * 2. - 1.,
+        view.velocity.y / PLAYER_SPEED,
+        view.gaps
+            .first()
+            .map_or(1., |gap| (gap.x - view.bird.x) / field.width),
+        gap_offset(0) / field.height,
+        gap_offset(1) / field.height,
+    ]
+}
+
+// Flaps when the single tanh output is positive
+pub fn decide(genome: &Genome, inputs: &[f32; INPUTS]) -> bool {
+    let weights = &genome.weights;
+    let mut output = weights[GENOME_LENGTH - 1];
+    for neuron in 0..HIDDEN {
+        let row = &weights[neuron * (INPUTS + 1)..(neuron + 1) * (INPUTS + 1)];
+        let sum = row[INPUTS]
+            + row[..INPUTS]
+                .iter()
+                .zip(inputs)
+                .map(|(weight, input)| weight * input)
+                .sum::<f32>();
+        output += weights[HIDDEN * (INPUTS + 1) + neuron] * sum.tanh();
+    }
+    output.tanh() > 0.
+}
+
+pub struct NeuralPilot {
+    pub genome: Genome,
+}
+
+impl Pilot for NeuralPilot {
+    fn name(&self) -> &str {
+        "neural network"
+    }
+
+    fn should_flap(&mut self, view: &PilotView) -> bool {
+        decide(&self.genome, &features(view))
+    }
+}
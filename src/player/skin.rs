@@ -0,0 +1,305 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{ui::settings::Settings, FlappybirdState};
+
+use super::{
+    animation::{AnimationIndices, AnimationTimer},
+    Seat, BIRD_BODY,
+};
+
+// Skins shipped with the game, each one lives next to its sprite sheet
+const BUILTIN_SKINS: [&str; 3] = [
+    include_str!("../../assets/sprites/birds/yellow/skin.ron"),
+    include_str!("../../assets/sprites/birds/red/skin.ron"),
+    include_str!("../../assets/sprites/birds/blue/skin.ron"),
+];
+
+// The bird's body in sprite pixels, a capsule lying along the wings
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct SkinCollider {
+    pub half_length: f32,
+    pub radius: f32,
+}
+
+impl SkinCollider {
+    pub fn collider(&self) -> Collider {
+        Collider::capsule_x(self.half_length, self.radius)
+    }
+}
+
+// A skin.ron, the sheet is a single row of frames and the path is relative to the assets
+#[derive(Debug, Clone, Deserialize)]
+pub struct SkinManifest {
+    pub name: String,
+    pub sheet: String,
+    pub frame_size: (u32, u32),
+    pub frames: usize,
+    pub frame_duration: f32,
+    pub collider: SkinCollider,
+}
+
+impl SkinManifest {
+    // Mods bring their own manifests, anything the animation can't play is turned away
+    pub fn validate(&self) -> Result<(), String> {
+        let (width, height) = self.frame_size;
+        if width == 0 || height == 0 || self.frames == 0 {
+            return Err("needs at least one frame of at least a pixel".to_string());
+        }
+        if self.frame_duration <= 0. || Duration::try_from_secs_f32(self.frame_duration).is_err() {
+            return Err(format!(
+                "frame_duration {} has to be a positive number of seconds",
+                self.frame_duration
+            ));
+        }
+        let SkinCollider {
+            half_length,
+            radius,
+        } = self.collider;
+        if !half_length.is_finite() || half_length < 0. || !radius.is_finite() || radius <= 0. {
+            return Err(format!(
+                "collider half_length {} and radius {} have to be sizes in pixels",
+                half_length, radius
+            ));
+        }
+        if (half_length + radius) * 2. > width as f32 || radius * 2. > height as f32 {
+            return Err(format!(
+                "collider half_length {} and radius {} don't fit in a {}x{} frame",
+                half_length, radius, width, height
+            ));
+        }
+        // the course is laid out for the built-in bird, a bigger body could meet gaps
+        // it can't fit through
+        if half_length + radius > BIRD_BODY.half_length + BIRD_BODY.radius
+            || radius > BIRD_BODY.radius
+        {
+            return Err(format!(
+                "collider half_length {} and radius {} are bigger than the bird's {} and {}",
+                half_length, radius, BIRD_BODY.half_length, BIRD_BODY.radius
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub struct Skin {
+    pub manifest: SkinManifest,
+    pub texture: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+}
+
+impl Skin {
+    pub fn animation_indices(&self) -> AnimationIndices {
+        AnimationIndices {
+            first: 0,
+            last: self.manifest.frames.saturating_sub(1),
+        }
+    }
+
+    pub fn animation_timer(&self) -> AnimationTimer {
+        AnimationTimer(Timer::new(
+            Duration::from_secs_f32(self.manifest.frame_duration),
+            TimerMode::Repeating,
+        ))
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct SkinRegistry {
+    pub skins: Vec<Skin>,
+}
+
+impl SkinRegistry {
+    // `source` is the asset source the sheet is read from, embedded or mods
+    pub fn register(
+        &mut self,
+        manifest: SkinManifest,
+        source: &str,
+        asset_server: &AssetServer,
+        texture_atlas_layouts: &mut Assets<TextureAtlasLayout>,
+    ) {
+        let (width, height) = manifest.frame_size;
+        let layout = TextureAtlasLayout::from_grid(
+            UVec2::new(width, height),
+            manifest.frames as u32,
+            1,
+            None,
+            None,
+        );
+        let skin = Skin {
+            texture: asset_server.load(format!("{}://{}", source, manifest.sheet)),
+            layout: texture_atlas_layouts.add(layout),
+            manifest,
+        };
+        // a later skin with the same name replaces the earlier one
+        match self
+            .skins
+            .iter()
+            .position(|existing| existing.manifest.name == skin.manifest.name)
+        {
+            Some(index) => self.skins[index] = skin,
+            None => self.skins.push(skin),
+        }
+    }
+
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.skins
+            .iter()
+            .position(|skin| skin.manifest.name == name)
+    }
+
+    pub fn get(&self, index: usize) -> &Skin {
+        &self.skins[index.min(self.skins.len() - 1)]
+    }
+}
+
+// Which skin the birds are wearing right now, an index into the SkinRegistry
+#[derive(Resource, Debug, Default, PartialEq, Eq)]
+pub struct ActiveSkin(pub usize);
+
+// What the title screen's skin button says for the current settings
+pub fn skin_label(settings: &Settings) -> String {
+    if settings.random_skin {
+        "SKIN RANDOM".to_string()
+    } else {
+        format!("SKIN {}", settings.skin.to_uppercase())
+    }
+}
+
+// Steps through every skin and then random, before wrapping around
+pub fn next_skin(settings: &mut Settings, registry: &SkinRegistry) {
+    if settings.random_skin {
+        settings.random_skin = false;
+        settings.skin = registry.get(0).manifest.name.clone();
+        return;
+    }
+    match registry.index_of(&settings.skin) {
+        Some(index) if index + 1 < registry.skins.len() => {
+            settings.skin = registry.get(index + 1).manifest.name.clone();
+        }
+        _ => settings.random_skin = true,
+    }
+}
+
+pub fn load_skins(
+    mut registry: ResMut<SkinRegistry>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    for source in BUILTIN_SKINS {
+        let manifest: SkinManifest =
+            ron::from_str(source).expect("built-in skin manifests are valid");
+        if let Err(reason) = manifest.validate() {
+            panic!("built-in skin {} {}", manifest.name, reason);
+        }
+        registry.register(
+            manifest,
+            "embedded",
+            &asset_server,
+            &mut texture_atlas_layouts,
+        );
+    }
+}
+
+// Picks up the skin chosen in the settings, a random skin waits for the next run
+pub fn select_skin(
+    settings: Res<Settings>,
+    registry: Res<SkinRegistry>,
+    mut active: ResMut<ActiveSkin>,
+) {
+    if settings.random_skin {
+        return;
+    }
+    let Some(index) = registry.index_of(&settings.skin) else {
+        eprintln!(
+            "Unknown skin {}, keeping {}",
+            settings.skin,
+            registry.get(active.0).manifest.name
+        );
+        return;
+    };
+    active.set_if_neq(ActiveSkin(index));
+}
+
+// Random uses its own rng, the world rng is kept for the course
+fn randomize_skin(
+    settings: Res<Settings>,
+    registry: Res<SkinRegistry>,
+    mut active: ResMut<ActiveSkin>,
+) {
+    if settings.random_skin {
+        active.set_if_neq(ActiveSkin(
+            rand::thread_rng().gen_range(0..registry.skins.len()),
+        ));
+    }
+}
+
+// Dresses every bird, and any bird that joins later, in the active skin
+fn apply_skin(
+    registry: Res<SkinRegistry>,
+    active: Res<ActiveSkin>,
+    mut bird_query: Query<(
+        Ref<Seat>,
+        &mut Handle<Image>,
+        &mut TextureAtlas,
+        &mut AnimationIndices,
+        &mut AnimationTimer,
+        &mut Collider,
+    )>,
+) {
+    let skin = registry.get(active.0);
+    for (seat, mut texture, mut atlas, mut indices, mut timer, mut collider) in
+        bird_query.iter_mut()
+    {
+        if !active.is_changed() && !seat.is_added() {
+            continue;
+        }
+        *texture = skin.texture.clone();
+        atlas.layout = skin.layout.clone();
+        atlas.index = 0;
+        *indices = skin.animation_indices();
+        *timer = skin.animation_timer();
+        *collider = skin.manifest.collider.collider();
+    }
+}
+
+pub struct SkinPlugin;
+
+impl Plugin for SkinPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SkinRegistry>()
+            .init_resource::<ActiveSkin>()
+            .add_systems(PreStartup, (load_skins, select_skin).chain())
+            .add_systems(Update, select_skin.run_if(resource_changed::<Settings>))
+            .add_systems(OnEnter(FlappybirdState::TapTap), randomize_skin)
+            .add_systems(Update, apply_skin);
+    }
+}
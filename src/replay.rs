@@ -0,0 +1,575 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    cli::Args,
+    modes::GameMode,
+    player::{
+        controller::{
+            player_movement, FlapIntent, Flapped, InputSource, FLAP_IMPULSE, PLAYER_SPEED,
+        },
+        Player,
+    },
+    storage,
+    timestep::GameplaySet,
+    ui::titlescreen::start_run,
+    world::{
+        difficulty::{Difficulty, DifficultyPreset},
+        pipes::PIPE_SPACING,
+        WorldRng,
+    },
+    FlappybirdState, Playfield, PIXELS_PER_METER,
+};
+
+const REPLAY_MAGIC: &[u8; 4] = b"FBRP";
+const REPLAY_VERSION: u16 = 1;
+pub const REPLAY_EXTENSION: &str = "fbr";
+
+// The constants a run was played with, a replay only reproduces if these match,
+// the world speed is where the difficulty curve starts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tuning {
+    pub world_speed: f32,
+    pub player_speed: f32,
+    pub flap_impulse: f32,
+    pub pipe_spacing: f32,
+    pub pixels_per_meter: f32,
+}
+
+impl Tuning {
+    pub fn current(world_speed: f32) -> Self {
+        Self {
+            world_speed,
+            player_speed: PLAYER_SPEED,
+            flap_impulse: FLAP_IMPULSE,
+            pipe_spacing: PIPE_SPACING,
+            pixels_per_meter: PIXELS_PER_METER,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub seed: u64,
+    pub tuning: Tuning,
+    pub difficulty: DifficultyPreset,
+    pub score: u64,
+    // how many ticks the run lasted
+    pub ticks: u32,
+    // tick of every flap, in order
+    pub flaps: Vec<u32>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    BadMagic,
+    UnsupportedVersion(u16),
+    UnknownDifficulty(u8),
+    Truncated,
+    // a tick count that doesn't fit, the file was damaged or never was a replay
+    Corrupt,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(err) => write!(f, "{}", err),
+            ReplayError::BadMagic => write!(f, "not a replay file"),
+            ReplayError::UnsupportedVersion(version) => {
+                write!(f, "unsupported replay version {}", version)
+            }
+            ReplayError::UnknownDifficulty(byte) => {
+                write!(f, "unknown difficulty preset {}", byte)
+            }
+            ReplayError::Truncated => write!(f, "replay file is truncated"),
+            ReplayError::Corrupt => write!(f, "replay file is corrupt"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<std::io::Error> for ReplayError {
+    fn from(err: std::io::Error) -> Self {
+        ReplayError::Io(err)
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], ReplayError> {
+        if self.bytes.len() < N {
+            return Err(ReplayError::Truncated);
+        }
+        let (head, rest) = self.bytes.split_at(N);
+        self.bytes = rest;
+        Ok(head.try_into().unwrap())
+    }
+
+    fn u16(&mut self) -> Result<u16, ReplayError> {
+        Ok(u16::from_le_bytes(self.take()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, ReplayError> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+
+    fn f32(&mut self) -> Result<f32, ReplayError> {
+        Ok(f32::from_le_bytes(self.take()?))
+    }
+
+    fn varint(&mut self) -> Result<u64, ReplayError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let [byte] = self.take()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ReplayError::Truncated)
+    }
+
+    fn tick(&mut self) -> Result<u32, ReplayError> {
+        u32::try_from(self.varint()?).map_err(|_| ReplayError::Corrupt)
+    }
+}
+
+// Layout, little endian:
+//   "FBRP" | version u16 | seed u64 | 5 x f32 tuning | difficulty u8 | score varint
+//   | ticks varint | flap count varint | flap ticks as varint deltas from the previous flap
+impl Replay {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(48 + self.flaps.len());
+        bytes.extend_from_slice(REPLAY_MAGIC);
+        bytes.extend_from_slice(&REPLAY_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        for value in [
+            self.tuning.world_speed,
+            self.tuning.player_speed,
+            self.tuning.flap_impulse,
+            self.tuning.pipe_spacing,
+            self.tuning.pixels_per_meter,
+        ] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.push(self.difficulty.to_byte());
+        write_varint(&mut bytes, self.score);
+        write_varint(&mut bytes, self.ticks as u64);
+        write_varint(&mut bytes, self.flaps.len() as u64);
+        let mut previous = 0;
+        for &tick in &self.flaps {
+            write_varint(&mut bytes, (tick - previous) as u64);
+            previous = tick;
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ReplayError> {
+        let mut reader = Reader { bytes };
+        if &reader.take::<4>()? != REPLAY_MAGIC {
+            return Err(ReplayError::BadMagic);
+        }
+        let version = reader.u16()?;
+        if version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+        let seed = reader.u64()?;
+        let tuning = Tuning {
+            world_speed: reader.f32()?,
+            player_speed: reader.f32()?,
+            flap_impulse: reader.f32()?,
+            pipe_spacing: reader.f32()?,
+            pixels_per_meter: reader.f32()?,
+        };
+        let [byte] = reader.take::<1>()?;
+        let difficulty =
+            DifficultyPreset::from_byte(byte).ok_or(ReplayError::UnknownDifficulty(byte))?;
+        let score = reader.varint()?;
+        let ticks = reader.tick()?;
+        let count = reader.varint()?;
+        let mut flaps = Vec::new();
+        let mut tick = 0u32;
+        for _ in 0..count {
+            tick = tick
+                .checked_add(reader.tick()?)
+                .ok_or(ReplayError::Corrupt)?;
+            flaps.push(tick);
+        }
+        Ok(Self {
+            seed,
+            tuning,
+            difficulty,
+            score,
+            ticks,
+            flaps,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ReplayError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+}
+
+pub fn replays_dir() -> PathBuf {
+    storage::data_dir().join("replays")
+}
+
+// The highest scoring saved run on this course that still plays back the same
+pub fn best_replay_for_seed(
+    seed: u64,
+    tuning: Tuning,
+    difficulty: DifficultyPreset,
+) -> Option<Replay> {
+    let suffix = format!("-{}.{}", seed, REPLAY_EXTENSION);
+    fs::read_dir(replays_dir())
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().ends_with(&suffix))
+        .filter_map(|entry| Replay::load(&entry.path()).ok())
+        .filter(|replay| {
+            replay.seed == seed && replay.tuning == tuning && replay.difficulty == difficulty
+        })
+        .max_by_key(|replay| replay.score)
+}
+
+// Fixed ticks since the run started, the clock flaps are recorded against
+#[derive(Resource, Debug, Default)]
+pub struct RunTick(pub u32);
+
+#[derive(Resource, Debug, Default)]
+pub struct ReplayRecorder {
+    pub flaps: Vec<u32>,
+}
+
+#[derive(Resource, Debug)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    pub next: usize,
+    pub started: bool,
+}
+
+fn reset_run_tick(mut tick: ResMut<RunTick>, mut recorder: ResMut<ReplayRecorder>) {
+    tick.0 = 0;
+    recorder.flaps.clear();
+}
+
+// The run starts with the first flap, which still happens on the tap to start screen
+pub fn run_started(state: Res<State<FlappybirdState>>, recorder: Res<ReplayRecorder>) -> bool {
+    match state.get() {
+        FlappybirdState::InGame => true,
+        FlappybirdState::TapTap => !recorder.flaps.is_empty(),
+        _ => false,
+    }
+}
+
+pub fn advance_run_tick(mut tick: ResMut<RunTick>) {
+    tick.0 += 1;
+}
+
+pub fn record_flaps(
+    mut flapped: EventReader<Flapped>,
+    tick: Res<RunTick>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    for _ in flapped.read() {
+        recorder.flaps.push(tick.0);
+    }
+}
+
+fn save_replay(
+    recorder: Res<ReplayRecorder>,
+    input_source: Res<InputSource>,
+    rng: Res<WorldRng>,
+    difficulty: Res<Difficulty>,
+    tick: Res<RunTick>,
+    player_query: Query<&Player>,
+    mode: Res<GameMode>,
+) {
+    // a versus run interleaves two players' flaps, there is no single run to replay,
+    // and a level isn't a seeded course
+    if *input_source != InputSource::Human || matches!(*mode, GameMode::Versus | GameMode::Level) {
+        return;
+    }
+    let replay = Replay {
+        seed: rng.seed,
+        tuning: Tuning::current(difficulty.curve.start().speed),
+        difficulty: difficulty.preset,
+        score: player_query.get_single().map_or(0, |player| player.score),
+        ticks: tick.0,
+        flaps: recorder.flaps.clone(),
+    };
+    let file_name = format!(
+        "{}-{}.{}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        replay.seed,
+        REPLAY_EXTENSION
+    );
+    let path = replays_dir().join(file_name);
+    match replay.save(&path) {
+        Ok(()) => println!("Saved replay to {}", path.display()),
+        Err(err) => eprintln!("Failed to save replay {}: {}", path.display(), err),
+    }
+}
+
+fn load_replay(mut commands: Commands, args: Res<Args>, mut input_source: ResMut<InputSource>) {
+    let Some(path) = &args.replay else {
+        return;
+    };
+    let replay = match Replay::load(path) {
+        Ok(replay) => replay,
+        Err(err) => {
+            eprintln!("Failed to load replay {}: {}", path.display(), err);
+            return;
+        }
+    };
+    if replay.tuning != Tuning::current(replay.difficulty.curve().start().speed) {
+        eprintln!("Replay was recorded with different tuning, it may not play back the same");
+    }
+    println!(
+        "Playing back {} ({} flaps, scored {})",
+        path.display(),
+        replay.flaps.len(),
+        replay.score
+    );
+    *input_source = InputSource::Replay;
+    commands.insert_resource(ReplayPlayback {
+        replay,
+        next: 0,
+        started: false,
+    });
+}
+
+fn autostart_replay(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut playback: ResMut<ReplayPlayback>,
+    mut mode: ResMut<GameMode>,
+    mut state: ResMut<NextState<FlappybirdState>>,
+    mut player_query: Query<&mut Transform, With<Player>>,
+    playfield: Res<Playfield>,
+) {
+    if playback.started {
+        return;
+    }
+    let Ok(mut transform) = player_query.get_single_mut() else {
+        return;
+    };
+    playback.started = true;
+    *mode = GameMode::Endless;
+    start_run(
+        &mut commands,
+        &asset_server,
+        &mut state,
+        &mut transform,
+        &playfield,
+    );
+}
+
+fn feed_replay(
+    tick: Res<RunTick>,
+    mut playback: ResMut<ReplayPlayback>,
+    mut player_query: Query<&mut FlapIntent, With<Player>>,
+) {
+    let next = playback.next;
+    if playback
+        .replay
+        .flaps
+        .get(next)
+        .is_some_and(|&flap| flap <= tick.0)
+    {
+        playback.next += 1;
+        for mut intent in player_query.iter_mut() {
+            intent.0 = true;
+        }
+    }
+}
+
+fn report_replay(playback: Res<ReplayPlayback>, player_query: Query<&Player>) {
+    let score = player_query.get_single().map_or(0, |player| player.score);
+    println!(
+        "Replay finished with score {} (recorded {})",
+        score, playback.replay.score
+    );
+}
+
+// Hand control back to the player once the replayed run is dismissed
+fn finish_replay(mut commands: Commands, mut input_source: ResMut<InputSource>) {
+    *input_source = InputSource::Human;
+    commands.remove_resource::<ReplayPlayback>();
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunTick>()
+            .init_resource::<ReplayRecorder>()
+            .add_systems(Startup, load_replay)
+            .add_systems(OnEnter(FlappybirdState::TapTap), reset_run_tick)
+            .add_systems(
+                Update,
+                autostart_replay.run_if(
+                    in_state(FlappybirdState::MainTitle)
+                        .and_then(resource_exists::<ReplayPlayback>),
+                ),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    feed_replay
+                        .in_set(GameplaySet::Input)
+                        .run_if(resource_exists::<ReplayPlayback>),
+                    record_flaps
+                        .after(player_movement)
+                        .in_set(GameplaySet::Simulation),
+                )
+                    .run_if(
+                        in_state(FlappybirdState::TapTap)
+                            .or_else(in_state(FlappybirdState::InGame)),
+                    ),
+            )
+            .add_systems(
+                FixedUpdate,
+                advance_run_tick
+                    .after(record_flaps)
+                    .in_set(GameplaySet::Simulation)
+                    .run_if(run_started),
+            )
+            .add_systems(
+                OnEnter(FlappybirdState::GameOver),
+                (
+                    save_replay,
+                    report_replay.run_if(resource_exists::<ReplayPlayback>),
+                ),
+            )
+            .add_systems(
+                OnExit(FlappybirdState::GameOver),
+                finish_replay.run_if(resource_exists::<ReplayPlayback>),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay() -> Replay {
+        Replay {
+            seed: 42,
+            tuning: Tuning::current(150.),
+            difficulty: DifficultyPreset::Hard,
+            score: 7,
+            ticks: 1234,
+            flaps: vec![1, 30, 31, 200, 1233],
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let original = replay();
+        let read = Replay::from_bytes(&original.to_bytes()).unwrap();
+        assert_eq!(read.seed, original.seed);
+        assert_eq!(read.tuning, original.tuning);
+        assert_eq!(read.difficulty, original.difficulty);
+        assert_eq!(read.score, original.score);
+        assert_eq!(read.ticks, original.ticks);
+        assert_eq!(read.flaps, original.flaps);
+    }
+
+    #[test]
+    fn rejects_a_different_file() {
+        let mut bytes = replay().to_bytes();
+        bytes[0] = b'X';
+        assert!(matches!(
+            Replay::from_bytes(&bytes),
+            Err(ReplayError::BadMagic)
+        ));
+    }
+
+    #[test]
+    fn rejects_a_truncated_file() {
+        let bytes = replay().to_bytes();
+        for len in 0..bytes.len() {
+            assert!(Replay::from_bytes(&bytes[..len]).is_err(), "{len} bytes");
+        }
+    }
+
+    #[test]
+    fn rejects_ticks_past_u32() {
+        let mut header = replay().to_bytes();
+        header.truncate(4 + 2 + 8 + 5 * 4 + 1);
+        write_varint(&mut header, 7);
+
+        let mut bytes = header.clone();
+        write_varint(&mut bytes, u32::MAX as u64 + 1);
+        write_varint(&mut bytes, 0);
+        assert!(matches!(
+            Replay::from_bytes(&bytes),
+            Err(ReplayError::Corrupt)
+        ));
+
+        // every delta fits but they add up past the end of time
+        let mut bytes = header;
+        write_varint(&mut bytes, 10);
+        write_varint(&mut bytes, 2);
+        write_varint(&mut bytes, u32::MAX as u64);
+        write_varint(&mut bytes, 1);
+        assert!(matches!(
+            Replay::from_bytes(&bytes),
+            Err(ReplayError::Corrupt)
+        ));
+    }
+}
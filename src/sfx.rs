@@ -0,0 +1,69 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use bevy::prelude::*;
+use bevy_kira_audio::{Audio, AudioControl};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sfx {
+    Wing,
+    Point,
+    Hit,
+    Die,
+    Swooshing,
+}
+
+impl Sfx {
+    pub fn path(&self) -> &'static str {
+        match self {
+            Sfx::Wing => "embedded://audio/sfx_wing.ogg",
+            Sfx::Point => "embedded://audio/sfx_point.ogg",
+            Sfx::Hit => "embedded://audio/sfx_hit.ogg",
+            Sfx::Die => "embedded://audio/sfx_die.ogg",
+            Sfx::Swooshing => "embedded://audio/sfx_swooshing.ogg",
+        }
+    }
+}
+
+// Gameplay asks for sounds through this event so it can run without an audio device
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlaySfx(pub Sfx);
+
+fn play_sfx(mut events: EventReader<PlaySfx>, audio: Res<Audio>, asset_server: Res<AssetServer>) {
+    for PlaySfx(sfx) in events.read() {
+        audio.play(
+            asset_server
+                .get_handle(sfx.path())
+                .unwrap_or_else(|| asset_server.load(sfx.path())),
+        );
+    }
+}
+
+pub struct SfxPlugin;
+
+impl Plugin for SfxPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, play_sfx);
+    }
+}
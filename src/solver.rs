@@ -0,0 +1,262 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::collections::HashSet;
+
+use crate::{
+    player::{BIRD_BODY, BIRD_SCALE},
+    timestep::TICK_RATE,
+    world::{
+        course::{fly, next_gap, GAP_STEP, GRAVITY},
+        difficulty::{Difficulty, DifficultyPreset},
+        ground::GROUND_TOP,
+        pipes::{PIPE_DESPAWN_X, PIPE_HALF_WIDTH, PIPE_SCALE, PIPE_SPACING, SENSOR_HALF_WIDTH},
+        PipeSpawner, WorldRng,
+    },
+    Playfield,
+};
+
+// The biggest bird's capsule as a box, a little stricter than the physics around the corners
+const BIRD_HALF_WIDTH: f32 = (BIRD_BODY.half_length + BIRD_BODY.radius) * BIRD_SCALE;
+const BIRD_HALF_HEIGHT: f32 = BIRD_BODY.radius * BIRD_SCALE;
+const PIPE_REACH: f32 = PIPE_HALF_WIDTH * PIPE_SCALE;
+const SENSOR_REACH: f32 = SENSOR_HALF_WIDTH * PIPE_SCALE;
+// Birds closer than this are the same bird to the search, the speed is one tick of gravity
+const Y_BUCKET: f32 = 3.;
+const VY_BUCKET: f32 = GRAVITY / TICK_RATE as f32;
+
+struct CoursePipe {
+    x: f32,
+    center: f32,
+    half_opening: f32,
+    passed: bool,
+}
+
+// A run without the bird, scrolls and spawns pipes the way the game's systems do
+struct Course {
+    rng: WorldRng,
+    difficulty: Difficulty,
+    spawner: PipeSpawner,
+    pipes: Vec<CoursePipe>,
+    playfield: Playfield,
+    score: u64,
+    // a hand-built course keeps the pipes it was given
+    generated: bool,
+}
+
+impl Course {
+    fn new(seed: u64, preset: DifficultyPreset, playfield: Playfield) -> Self {
+        Self {
+            rng: WorldRng::new(seed),
+            difficulty: Difficulty::new(preset),
+            spawner: PipeSpawner::default(),
+            pipes: Vec::new(),
+            playfield,
+            score: 0,
+            generated: true,
+        }
+    }
+
+    fn advance(&mut self) {
+        self.difficulty.current = self.difficulty.curve.at(self.score);
+        let delta = self.difficulty.current.speed / TICK_RATE as f32;
+
+        for pipe in self.pipes.iter_mut() {
+            pipe.x -= delta;
+        }
+        self.spawner.travelled += delta;
+        if self.generated && self.spawner.travelled >= PIPE_SPACING {
+            self.spawner.travelled -= PIPE_SPACING;
+            let gap = next_gap(
+                &mut *self.rng,
+                self.spawner.last_gap,
+                &self.difficulty.current,
+                self.difficulty.curve.fairness,
+            );
+            self.spawner.last_gap = gap;
+            self.pipes.push(CoursePipe {
+                x: self.playfield.width + 50. - self.spawner.travelled,
+                center: self.playfield.height / 2. + gap as f32 * GAP_STEP,
+                // the pipes sit half the gap off the middle, drawn at twice the size
+                half_opening: self.difficulty.current.gap,
+                passed: false,
+            });
+        }
+        self.pipes.retain(|pipe| pipe.x > PIPE_DESPAWN_X);
+    }
+
+    fn hits(&self, bird: &Bird) -> bool {
+        if bird.y - BIRD_HALF_HEIGHT < GROUND_TOP {
+            return true;
+        }
+        self.pipes.iter().any(|pipe| {
+            (pipe.x - bird.x).abs() < PIPE_REACH + BIRD_HALF_WIDTH
+                && (bird.y + BIRD_HALF_HEIGHT > pipe.center + pipe.half_opening
+                    || bird.y - BIRD_HALF_HEIGHT < pipe.center - pipe.half_opening)
+        })
+    }
+
+    // Every bird still flying is at the same x, so they all pass a pipe together
+    fn count_passed(&mut self, bird_x: f32) {
+        for pipe in self.pipes.iter_mut() {
+            if !pipe.passed && pipe.x + SENSOR_REACH < bird_x - BIRD_HALF_WIDTH {
+                pipe.passed = true;
+                self.score += 1;
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Bird {
+    x: f32,
+    y: f32,
+    vy: f32,
+}
+
+impl Bird {
+    // The same flight the course generator plans with, above the playfield flaps are ignored
+    fn step(mut self, flap: bool, playfield: &Playfield) -> Self {
+        (self.y, self.vy) = fly(self.y, self.vy, flap && self.y < playfield.height);
+        self
+    }
+
+    fn bucket(&self) -> (i32, i32) {
+        (
+            (self.y / Y_BUCKET).round() as i32,
+            (self.vy / VY_BUCKET).round() as i32,
+        )
+    }
+}
+
+// How far the best possible bird got on a course
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Verdict {
+    pub seed: u64,
+    pub score: u64,
+    pub ticks: u32,
+    // reached the target score, the course may well go on being beatable after
+    pub beaten: bool,
+}
+
+// Searches every flap timing at once, one tick at a time, keeping one bird per bucket of
+// height and speed. The course doesn't depend on how the bird flies, only on the score,
+// and every bird still alive has the same score
+pub fn solve(seed: u64, preset: DifficultyPreset, playfield: Playfield, target: u64) -> Verdict {
+    search(Course::new(seed, preset, playfield), seed, target)
+}
+
+fn search(mut course: Course, seed: u64, target: u64) -> Verdict {
+    let playfield = course.playfield;
+    let mut birds = vec![Bird {
+        x: playfield.width / 6.,
+        y: playfield.height / 2.,
+        vy: 0.,
+    }];
+    let mut ticks = 0;
+    loop {
+        course.advance();
+        ticks += 1;
+
+        let mut seen = HashSet::new();
+        let mut next = Vec::new();
+        // every run starts with a flap
+        let choices: &[bool] = if ticks == 1 { &[true] } else { &[false, true] };
+        for bird in birds.iter() {
+            for &flap in choices {
+                let moved = bird.step(flap, &playfield);
+                if !course.hits(&moved) && seen.insert(moved.bucket()) {
+                    next.push(moved);
+                }
+            }
+        }
+        if next.is_empty() {
+            return Verdict {
+                seed,
+                score: course.score,
+                ticks,
+                beaten: false,
+            };
+        }
+        birds = next;
+
+        course.count_passed(playfield.width / 6.);
+        if course.score >= target {
+            return Verdict {
+                seed,
+                score: course.score,
+                ticks,
+                beaten: true,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::course::reach;
+
+    const BIRD_X: f32 = 1280. / 6.;
+
+    // Pipes one spacing apart, the first a little way ahead of the bird
+    fn course(preset: DifficultyPreset, centers: &[f32], half_opening: f32) -> Course {
+        let mut course = Course::new(0, preset, Playfield::default());
+        course.generated = false;
+        course.pipes = centers
+            .iter()
+            .enumerate()
+            .map(|(index, &center)| CoursePipe {
+                x: BIRD_X + 300. + index as f32 * PIPE_SPACING,
+                center,
+                half_opening,
+                passed: false,
+            })
+            .collect();
+        course
+    }
+
+    #[test]
+    fn level_pipes_are_beatable() {
+        let course = course(DifficultyPreset::Normal, &[360., 320., 400.], 80.);
+        let verdict = search(course, 0, 3);
+        assert!(verdict.beaten, "{verdict:?}");
+        assert_eq!(verdict.score, 3);
+    }
+
+    #[test]
+    fn a_gap_past_the_flap_reach_is_not() {
+        let preset = DifficultyPreset::Hard;
+        let half_opening = 80.;
+        let (rise, _) = reach(preset.curve().start().speed);
+        let low = 160.;
+        // the bird leaves the top of one opening and has to make the bottom of the next
+        let high = low + rise + 2. * half_opening + GAP_STEP;
+        assert!(high < Playfield::default().height);
+
+        let verdict = search(course(preset, &[low, high], half_opening), 0, 2);
+        assert!(!verdict.beaten, "{verdict:?}");
+        assert!(verdict.score < 2);
+    }
+}
@@ -0,0 +1,87 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+// Where the game keeps its save files, FLAPPYBIRD_DATA_DIR wins if set
+pub fn data_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("FLAPPYBIRD_DATA_DIR") {
+        return PathBuf::from(dir);
+    }
+
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+            })
+    };
+
+    base.unwrap_or_else(|| PathBuf::from("."))
+        .join("flappybird")
+}
+
+pub fn load<T: DeserializeOwned>(file_name: &str) -> Option<T> {
+    load_path(&data_dir().join(file_name))
+}
+
+pub fn load_path<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let contents = fs::read_to_string(path).ok()?;
+    match ron::from_str(&contents) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            eprintln!("Failed to parse {}: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+pub fn save<T: Serialize>(file_name: &str, value: &T) {
+    let dir = data_dir();
+    if let Err(err) = fs::create_dir_all(&dir) {
+        eprintln!("Failed to create {}: {}", dir.display(), err);
+        return;
+    }
+
+    let path = dir.join(file_name);
+    let contents = match ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()) {
+        Ok(contents) => contents,
+        Err(err) => {
+            eprintln!("Failed to serialize {}: {}", path.display(), err);
+            return;
+        }
+    };
+    if let Err(err) = fs::write(&path, contents) {
+        eprintln!("Failed to write {}: {}", path.display(), err);
+    }
+}
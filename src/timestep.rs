@@ -0,0 +1,123 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use bevy::{prelude::*, transform::TransformSystem};
+use bevy_rapier2d::prelude::*;
+
+// Gameplay runs at this rate no matter how fast the screen refreshes
+pub const TICK_RATE: f64 = 60.0;
+
+// Ordering of the gameplay systems around the physics step inside FixedUpdate,
+// everything is ordered so a seed and a list of flaps always play out the same
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GameplaySet {
+    // feeding flaps to the birds
+    Input,
+    // moving the bird and scrolling the world
+    Simulation,
+    // reacting to what the physics step reported
+    Collisions,
+}
+
+// Lets entities moved in FixedUpdate be drawn between their last two simulated positions
+#[derive(Debug, Component, Default)]
+pub struct RenderInterpolation {
+    previous: Transform,
+    current: Transform,
+}
+
+// Put the simulated transforms back before the fixed loop gets to see them
+fn restore_simulated_transforms(mut query: Query<(&mut Transform, Ref<RenderInterpolation>)>) {
+    for (mut transform, interpolation) in query.iter_mut() {
+        // Nothing simulated yet, the spawn transform is the simulated one
+        if interpolation.is_added() {
+            continue;
+        }
+        *transform = interpolation.current;
+    }
+}
+
+fn store_previous_transforms(mut query: Query<(&Transform, &mut RenderInterpolation)>) {
+    for (transform, mut interpolation) in query.iter_mut() {
+        interpolation.previous = *transform;
+    }
+}
+
+fn store_current_transforms(mut query: Query<(&Transform, &mut RenderInterpolation)>) {
+    for (transform, mut interpolation) in query.iter_mut() {
+        // Spawned during this step, there is nothing to interpolate from yet
+        if interpolation.is_added() {
+            interpolation.previous = *transform;
+        }
+        interpolation.current = *transform;
+    }
+}
+
+fn interpolate_transforms(
+    time: Res<Time<Fixed>>,
+    mut query: Query<(&mut Transform, &mut RenderInterpolation)>,
+) {
+    let alpha = time.overstep_fraction();
+    for (mut transform, mut interpolation) in query.iter_mut() {
+        // Moved outside of the fixed loop (a reset or a new spawn), snap instead of sliding there
+        if *transform != interpolation.current {
+            interpolation.previous = *transform;
+            interpolation.current = *transform;
+            continue;
+        }
+        let previous = interpolation.previous;
+        let current = interpolation.current;
+        transform.translation = previous.translation.lerp(current.translation, alpha);
+        transform.rotation = previous.rotation.slerp(current.rotation, alpha);
+    }
+}
+
+pub struct TimestepPlugin;
+
+impl Plugin for TimestepPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Time::<Fixed>::from_hz(TICK_RATE))
+            .insert_resource(TimestepMode::Fixed {
+                dt: (1. / TICK_RATE) as f32,
+                substeps: 1,
+            })
+            .configure_sets(
+                FixedUpdate,
+                (GameplaySet::Input, GameplaySet::Simulation)
+                    .chain()
+                    .before(PhysicsSet::SyncBackend),
+            )
+            .configure_sets(
+                FixedUpdate,
+                GameplaySet::Collisions.after(PhysicsSet::Writeback),
+            )
+            .add_systems(PreUpdate, restore_simulated_transforms)
+            .add_systems(FixedFirst, store_previous_transforms)
+            .add_systems(FixedLast, store_current_transforms)
+            .add_systems(
+                PostUpdate,
+                interpolate_transforms.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
@@ -21,3 +21,306 @@ LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
 OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
+
+use bevy::prelude::*;
+
+use crate::{
+    modes::{
+        daily::{record_daily_score, today, DailyRecords},
+        demo::DemoRun,
+        GameMode,
+    },
+    player::Player,
+    sfx::{PlaySfx, Sfx},
+    ui::{
+        leaderboard::{record_run, LastRun},
+        medal::{spawn_medal, Medal, MedalThresholds},
+        score::ScoreUI,
+        settings::Settings,
+        taptap::spawn_taptap_screen,
+    },
+    world::WorldRng,
+    FlappybirdState,
+};
+
+#[derive(Component)]
+pub struct GameOverUI;
+
+#[derive(Debug, Component, Clone, Copy)]
+pub enum GameOverButtons {
+    Play,
+    Leaderboard,
+    Menu,
+    Levels,
+}
+
+#[derive(Resource, Default)]
+pub struct BestScore(pub u64);
+
+// Everything the scoreboard panel shows about the run that just ended
+pub struct RunSummary {
+    pub score: u64,
+    pub best: u64,
+    pub medal: Option<Medal>,
+    pub new_entry: bool,
+    pub seed: u64,
+}
+
+fn spawn_panel_number(parent: &mut ChildBuilder, asset_server: &AssetServer, value: u64, top: f32) {
+    for (font, color) in [
+        ("embedded://fonts/inside.ttf", Color::WHITE),
+        ("embedded://fonts/outside.ttf", Color::BLACK),
+    ] {
+        parent.spawn(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(top),
+                right: Val::Px(11. * 3.),
+                ..default()
+            },
+            text: Text::from_section(
+                value.to_string(),
+                TextStyle {
+                    font: asset_server.load(font),
+                    font_size: 30.0,
+                    color,
+                },
+            )
+            .with_justify(JustifyText::Right),
+            ..default()
+        });
+    }
+}
+
+pub fn spawn_gameover_screen(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    summary: &RunSummary,
+) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(20.0),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(GameOverUI)
+        .with_children(|parent| {
+            parent.spawn(ImageBundle {
+                style: Style {
+                    width: Val::Px(96. * 3.),
+                    height: Val::Px(25. * 3.),
+                    ..default()
+                },
+                image: UiImage::new(asset_server.load("embedded://sprites/ui/gameover.png")),
+                ..Default::default()
+            });
+
+            // Scoreboard panel, the numbers sit under the SCORE and BEST labels
+            parent
+                .spawn(ImageBundle {
+                    style: Style {
+                        width: Val::Px(113. * 3.),
+                        height: Val::Px(59. * 3.),
+                        ..default()
+                    },
+                    image: UiImage::new(asset_server.load("embedded://sprites/ui/scoreboard.png")),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    spawn_panel_number(parent, asset_server, summary.score, 16. * 3.);
+                    spawn_panel_number(parent, asset_server, summary.best, 37. * 3.);
+                    if let Some(medal) = summary.medal {
+                        spawn_medal(parent, asset_server, medal);
+                    }
+                    if summary.new_entry {
+                        parent.spawn(ImageBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                left: Val::Px(66. * 3.),
+                                top: Val::Px(29. * 3.),
+                                width: Val::Px(16. * 3.),
+                                height: Val::Px(7. * 3.),
+                                ..default()
+                            },
+                            image: UiImage::new(asset_server.load("embedded://sprites/ui/new.png")),
+                            ..Default::default()
+                        });
+                    }
+                });
+
+            // Shown so a course can be shared and replayed with --seed
+            parent.spawn(
+                TextBundle::from_section(
+                    format!("SEED {}", summary.seed),
+                    TextStyle {
+                        font: asset_server.load("embedded://fonts/pixel.ttf"),
+                        font_size: 24.0,
+                        ..default()
+                    },
+                )
+                .with_text_justify(JustifyText::Center),
+            );
+
+            // Buttons node
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Auto,
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        flex_direction: FlexDirection::Row,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn(ButtonBundle {
+                            style: Style {
+                                width: Val::Px(31. * 3.),
+                                height: Val::Px(18. * 3.),
+                                margin: UiRect::horizontal(Val::Px(10.0)),
+                                ..default()
+                            },
+                            image: UiImage::new(
+                                asset_server.load("embedded://sprites/ui/play.png"),
+                            ),
+                            ..Default::default()
+                        })
+                        .insert(GameOverButtons::Play);
+
+                    parent
+                        .spawn(ButtonBundle {
+                            style: Style {
+                                width: Val::Px(31. * 3.),
+                                height: Val::Px(18. * 3.),
+                                margin: UiRect::horizontal(Val::Px(10.0)),
+                                ..default()
+                            },
+                            image: UiImage::new(
+                                asset_server.load("embedded://sprites/ui/board.png"),
+                            ),
+                            ..Default::default()
+                        })
+                        .insert(GameOverButtons::Leaderboard);
+                });
+        });
+}
+
+fn show_gameover_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    player_query: Query<&Player>,
+    mut best_score: ResMut<BestScore>,
+    thresholds: Res<MedalThresholds>,
+    last_run: Res<LastRun>,
+    rng: Res<WorldRng>,
+    mode: Res<GameMode>,
+    daily: Res<DailyRecords>,
+) {
+    let score = player_query.get_single().map_or(0, |player| player.score);
+    let best = match *mode {
+        GameMode::Endless => {
+            best_score.0 = best_score.0.max(score);
+            best_score.0
+        }
+        GameMode::Daily => daily.record(today()).best,
+        // these modes show their own results
+        GameMode::Versus | GameMode::Level => return,
+    };
+    spawn_gameover_screen(
+        &mut commands,
+        &asset_server,
+        &RunSummary {
+            score,
+            best,
+            medal: thresholds.medal_for(score),
+            new_entry: last_run.rank.is_some(),
+            seed: rng.seed,
+        },
+    );
+}
+
+fn gameover_button_system(
+    mut commands: Commands,
+    interaction_query: Query<(&Interaction, &GameOverButtons), Changed<Interaction>>,
+    asset_server: Res<AssetServer>,
+    mut sfx: EventWriter<PlaySfx>,
+    mut state: ResMut<NextState<FlappybirdState>>,
+    mode: Res<GameMode>,
+    daily: Res<DailyRecords>,
+    settings: Res<Settings>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        sfx.send(PlaySfx(Sfx::Swooshing));
+        match button {
+            GameOverButtons::Play => {
+                println!("Play again clicked!");
+                if *mode == GameMode::Daily && daily.attempts_left(today(), &settings) == 0 {
+                    println!("No daily attempts left today");
+                    state.set(FlappybirdState::MainTitle);
+                    continue;
+                }
+                spawn_taptap_screen(&mut commands, &asset_server);
+                state.set(FlappybirdState::TapTap);
+            }
+            GameOverButtons::Leaderboard => {
+                println!("Leaderboard button clicked!");
+                state.set(FlappybirdState::Scoreboard);
+            }
+            GameOverButtons::Menu => {
+                println!("Menu button clicked!");
+                state.set(FlappybirdState::MainTitle);
+            }
+            GameOverButtons::Levels => {
+                println!("Levels button clicked!");
+                state.set(FlappybirdState::LevelSelect);
+            }
+        }
+    }
+}
+
+// The pipes and the bird reset themselves, the screen only has to clear its own UI
+fn reset_gameover_ui(
+    mut commands: Commands,
+    gameover_query: Query<Entity, With<GameOverUI>>,
+    mut score_query: Query<&mut Text, With<ScoreUI>>,
+) {
+    for entity in gameover_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    for mut text in score_query.iter_mut() {
+        text.sections[0].value = "0".to_string();
+    }
+}
+
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BestScore>()
+            .add_systems(
+                OnEnter(FlappybirdState::GameOver),
+                show_gameover_screen
+                    .after(record_run)
+                    .after(record_daily_score)
+                    .run_if(not(resource_exists::<DemoRun>)),
+            )
+            .add_systems(
+                Update,
+                gameover_button_system.run_if(in_state(FlappybirdState::GameOver)),
+            )
+            .add_systems(OnExit(FlappybirdState::GameOver), reset_gameover_ui);
+    }
+}
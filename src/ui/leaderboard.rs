@@ -21,3 +21,533 @@ LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
 OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
+
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    modes::GameMode,
+    player::{controller::InputSource, Player},
+    sfx::{PlaySfx, Sfx},
+    storage,
+    ui::gameover::BestScore,
+    world::difficulty::{reset_difficulty, Difficulty, DifficultyPreset},
+    FlappybirdState,
+};
+
+const LEADERBOARD_FILE: &str = "leaderboard.ron";
+const LEADERBOARD_VERSION: u32 = 1;
+// per difficulty preset, every preset keeps a table of its own
+pub const LEADERBOARD_SIZE: usize = 25;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub score: u64,
+    // unix timestamp in seconds
+    pub timestamp: i64,
+    pub difficulty: DifficultyPreset,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LeaderboardFile {
+    version: u32,
+    entries: Vec<LeaderboardEntry>,
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct Leaderboard {
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+impl Leaderboard {
+    pub fn load() -> Self {
+        match storage::load::<LeaderboardFile>(LEADERBOARD_FILE) {
+            Some(file) if file.version == LEADERBOARD_VERSION => Self {
+                entries: file.entries,
+            },
+            Some(file) => {
+                eprintln!("Ignoring leaderboard with unknown version {}", file.version);
+                Self::default()
+            }
+            None => Self::default(),
+        }
+    }
+
+    pub fn save(&self) {
+        storage::save(
+            LEADERBOARD_FILE,
+            &LeaderboardFile {
+                version: LEADERBOARD_VERSION,
+                entries: self.entries.clone(),
+            },
+        );
+    }
+
+    // The table of one preset, best first
+    pub fn entries(
+        &self,
+        difficulty: DifficultyPreset,
+    ) -> impl Iterator<Item = &LeaderboardEntry> + '_ {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.difficulty == difficulty)
+    }
+
+    pub fn best(&self, difficulty: DifficultyPreset) -> u64 {
+        self.entries(difficulty)
+            .next()
+            .map_or(0, |entry| entry.score)
+    }
+
+    // Returns the rank the entry landed on in its preset's table, or None if it didn't make it
+    pub fn submit(&mut self, entry: LeaderboardEntry) -> Option<usize> {
+        let difficulty = entry.difficulty;
+        let rank = self
+            .entries(difficulty)
+            .position(|other| entry.score > other.score)
+            .unwrap_or_else(|| self.entries(difficulty).count());
+        if rank >= LEADERBOARD_SIZE {
+            return None;
+        }
+        let index = self
+            .entries
+            .iter()
+            .position(|other| entry.score > other.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(index, entry);
+        let mut kept = 0;
+        self.entries.retain(|entry| {
+            if entry.difficulty != difficulty {
+                return true;
+            }
+            kept += 1;
+            kept <= LEADERBOARD_SIZE
+        });
+        Some(rank)
+    }
+}
+
+// Where the last finished run ended up in the table
+#[derive(Resource, Debug, Default)]
+pub struct LastRun {
+    pub rank: Option<usize>,
+}
+
+pub fn record_run(
+    player_query: Query<&Player>,
+    mode: Res<GameMode>,
+    input_source: Res<InputSource>,
+    difficulty: Res<Difficulty>,
+    mut leaderboard: ResMut<Leaderboard>,
+    mut last_run: ResMut<LastRun>,
+) {
+    last_run.rank = None;
+    if *mode != GameMode::Endless || *input_source != InputSource::Human {
+        return;
+    }
+    let Ok(player) = player_query.get_single() else {
+        return;
+    };
+    if player.score == 0 {
+        return;
+    }
+
+    last_run.rank = leaderboard.submit(LeaderboardEntry {
+        name: player.name.clone(),
+        score: player.score,
+        timestamp: chrono::Local::now().timestamp(),
+        difficulty: difficulty.preset,
+    });
+    if last_run.rank.is_some() {
+        leaderboard.save();
+    }
+}
+
+#[derive(Component)]
+pub struct ScoreboardUI;
+
+#[derive(Component)]
+pub struct ScoreboardBackButton;
+
+#[derive(Component, Default)]
+struct ScrollingList {
+    position: f32,
+}
+
+fn spawn_row(
+    parent: &mut ChildBuilder,
+    asset_server: &AssetServer,
+    rank: usize,
+    entry: &LeaderboardEntry,
+    is_new: bool,
+) {
+    let text_style = TextStyle {
+        font: asset_server.load("embedded://fonts/pixel.ttf"),
+        font_size: 24.0,
+        color: if is_new {
+            Color::srgb(0.98, 0.47, 0.35)
+        } else {
+            Color::WHITE
+        },
+    };
+    let date = chrono::DateTime::from_timestamp(entry.timestamp, 0)
+        .map(|date| {
+            date.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d")
+                .to_string()
+        })
+        .unwrap_or_default();
+
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Px(36.0),
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(16.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            for (text, width) in [
+                (format!("{}.", rank + 1), 48.),
+                (entry.name.clone(), 180.),
+                (entry.score.to_string(), 72.),
+                (date, 150.),
+            ] {
+                parent.spawn(TextBundle {
+                    style: Style {
+                        width: Val::Px(width),
+                        ..default()
+                    },
+                    text: Text::from_section(text, text_style.clone()),
+                    ..default()
+                });
+            }
+            if is_new {
+                parent.spawn(ImageBundle {
+                    style: Style {
+                        width: Val::Px(16. * 3.),
+                        height: Val::Px(7. * 3.),
+                        ..default()
+                    },
+                    image: UiImage::new(asset_server.load("embedded://sprites/ui/new.png")),
+                    ..Default::default()
+                });
+            }
+        });
+}
+
+pub fn spawn_scoreboard_screen(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    leaderboard: &Leaderboard,
+    difficulty: DifficultyPreset,
+    new_rank: Option<usize>,
+) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(20.0),
+                ..default()
+            },
+            background_color: Color::srgba(0., 0., 0., 0.6).into(),
+            ..default()
+        })
+        .insert(ScoreboardUI)
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(20.0),
+                        left: Val::Px(20.0),
+                        width: Val::Px(7. * 3.),
+                        height: Val::Px(10. * 3.),
+                        ..default()
+                    },
+                    image: UiImage::new(asset_server.load("embedded://sprites/ui/back.png")),
+                    ..Default::default()
+                })
+                .insert(ScoreboardBackButton);
+
+            parent.spawn(
+                TextBundle::from_section(
+                    "LEADERBOARD",
+                    TextStyle {
+                        font: asset_server.load("embedded://fonts/pixel.ttf"),
+                        font_size: 48.0,
+                        ..default()
+                    },
+                )
+                .with_text_justify(JustifyText::Center),
+            );
+            parent.spawn(TextBundle::from_section(
+                difficulty.label(),
+                TextStyle {
+                    font: asset_server.load("embedded://fonts/pixel.ttf"),
+                    font_size: 24.0,
+                    ..default()
+                },
+            ));
+
+            // List container, the list inside moves when scrolled
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(560.0),
+                        height: Val::Percent(60.0),
+                        flex_direction: FlexDirection::Column,
+                        overflow: Overflow::clip_y(),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn((
+                            NodeBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::Column,
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            ScrollingList::default(),
+                        ))
+                        .with_children(|parent| {
+                            if leaderboard.entries(difficulty).next().is_none() {
+                                parent.spawn(TextBundle::from_section(
+                                    "No scores yet!",
+                                    TextStyle {
+                                        font: asset_server.load("embedded://fonts/pixel.ttf"),
+                                        font_size: 24.0,
+                                        ..default()
+                                    },
+                                ));
+                            }
+                            for (rank, entry) in leaderboard.entries(difficulty).enumerate() {
+                                spawn_row(
+                                    parent,
+                                    asset_server,
+                                    rank,
+                                    entry,
+                                    new_rank == Some(rank),
+                                );
+                            }
+                        });
+                });
+        });
+}
+
+fn show_scoreboard_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    leaderboard: Res<Leaderboard>,
+    difficulty: Res<Difficulty>,
+    last_run: Res<LastRun>,
+) {
+    spawn_scoreboard_screen(
+        &mut commands,
+        &asset_server,
+        &leaderboard,
+        difficulty.preset,
+        last_run.rank,
+    );
+}
+
+// The medals and the game over board compare against the best on the same preset
+fn load_best_score(
+    leaderboard: Res<Leaderboard>,
+    difficulty: Res<Difficulty>,
+    mut best_score: ResMut<BestScore>,
+) {
+    best_score.0 = leaderboard.best(difficulty.preset);
+}
+
+fn despawn_scoreboard_screen(mut commands: Commands, query: Query<Entity, With<ScoreboardUI>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn scroll_scoreboard(
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut list_query: Query<(&mut ScrollingList, &mut Style, &Parent, &Node)>,
+    node_query: Query<&Node>,
+) {
+    let mut delta_y = 0.;
+    for event in mouse_wheel_events.read() {
+        delta_y += match event.unit {
+            MouseScrollUnit::Line => event.y * 36.,
+            MouseScrollUnit::Pixel => event.y,
+        };
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        delta_y += 36.;
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        delta_y -= 36.;
+    }
+
+    for (mut list, mut style, parent, list_node) in &mut list_query {
+        let container_height = node_query.get(parent.get()).unwrap().size().y;
+        let max_scroll = (list_node.size().y - container_height).max(0.);
+        list.position = (list.position + delta_y).clamp(-max_scroll, 0.);
+        style.top = Val::Px(list.position);
+    }
+}
+
+fn scoreboard_button_system(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<ScoreboardBackButton>)>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut sfx: EventWriter<PlaySfx>,
+    mut state: ResMut<NextState<FlappybirdState>>,
+) {
+    let pressed = interaction_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+    if pressed || keyboard_input.just_pressed(KeyCode::Escape) {
+        println!("Scoreboard back clicked!");
+        sfx.send(PlaySfx(Sfx::Swooshing));
+        state.set(FlappybirdState::MainTitle);
+    }
+}
+
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Leaderboard::load())
+            .init_resource::<LastRun>()
+            .add_systems(
+                OnEnter(FlappybirdState::TapTap),
+                load_best_score.after(reset_difficulty),
+            )
+            .add_systems(OnEnter(FlappybirdState::GameOver), record_run)
+            .add_systems(OnEnter(FlappybirdState::Scoreboard), show_scoreboard_screen)
+            .add_systems(
+                OnExit(FlappybirdState::Scoreboard),
+                despawn_scoreboard_screen,
+            )
+            .add_systems(
+                Update,
+                (scroll_scoreboard, scoreboard_button_system)
+                    .run_if(in_state(FlappybirdState::Scoreboard)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, score: u64, difficulty: DifficultyPreset) -> LeaderboardEntry {
+        LeaderboardEntry {
+            name: name.to_string(),
+            score,
+            timestamp: 0,
+            difficulty,
+        }
+    }
+
+    fn scores(leaderboard: &Leaderboard, difficulty: DifficultyPreset) -> Vec<u64> {
+        leaderboard
+            .entries(difficulty)
+            .map(|entry| entry.score)
+            .collect()
+    }
+
+    #[test]
+    fn ranks_best_first() {
+        let mut leaderboard = Leaderboard::default();
+        assert_eq!(
+            leaderboard.submit(entry("a", 5, DifficultyPreset::Normal)),
+            Some(0)
+        );
+        assert_eq!(
+            leaderboard.submit(entry("b", 9, DifficultyPreset::Normal)),
+            Some(0)
+        );
+        assert_eq!(
+            leaderboard.submit(entry("c", 7, DifficultyPreset::Normal)),
+            Some(1)
+        );
+        assert_eq!(
+            scores(&leaderboard, DifficultyPreset::Normal),
+            vec![9, 7, 5]
+        );
+        assert_eq!(leaderboard.best(DifficultyPreset::Normal), 9);
+    }
+
+    #[test]
+    fn a_tie_goes_below_the_earlier_run() {
+        let mut leaderboard = Leaderboard::default();
+        leaderboard.submit(entry("first", 5, DifficultyPreset::Normal));
+        assert_eq!(
+            leaderboard.submit(entry("second", 5, DifficultyPreset::Normal)),
+            Some(1)
+        );
+        let names: Vec<&str> = leaderboard
+            .entries(DifficultyPreset::Normal)
+            .map(|entry| entry.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn a_full_table_keeps_the_best() {
+        let mut leaderboard = Leaderboard::default();
+        for score in 1..=LEADERBOARD_SIZE as u64 {
+            leaderboard.submit(entry("run", score, DifficultyPreset::Normal));
+        }
+        // no better than the last place, and tying it isn't enough
+        assert_eq!(
+            leaderboard.submit(entry("low", 0, DifficultyPreset::Normal)),
+            None
+        );
+        assert_eq!(
+            leaderboard.submit(entry("tie", 1, DifficultyPreset::Normal)),
+            None
+        );
+
+        assert_eq!(
+            leaderboard.submit(entry("high", 100, DifficultyPreset::Normal)),
+            Some(0)
+        );
+        let table = scores(&leaderboard, DifficultyPreset::Normal);
+        assert_eq!(table.len(), LEADERBOARD_SIZE);
+        assert_eq!(table[0], 100);
+        assert_eq!(*table.last().unwrap(), 2);
+    }
+
+    #[test]
+    fn presets_have_their_own_tables() {
+        let mut leaderboard = Leaderboard::default();
+        for score in 1..=LEADERBOARD_SIZE as u64 {
+            leaderboard.submit(entry("run", score * 10, DifficultyPreset::Hard));
+        }
+        assert_eq!(
+            leaderboard.submit(entry("easy", 1, DifficultyPreset::Easy)),
+            Some(0)
+        );
+        assert_eq!(leaderboard.best(DifficultyPreset::Easy), 1);
+        assert_eq!(leaderboard.best(DifficultyPreset::Normal), 0);
+        assert_eq!(
+            scores(&leaderboard, DifficultyPreset::Hard).len(),
+            LEADERBOARD_SIZE
+        );
+    }
+}
@@ -0,0 +1,237 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    player::animation::{AnimationIndices, AnimationTimer},
+    storage, FlappybirdState,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Medal {
+    Copper,
+    Silver,
+    Gold,
+    Platinum,
+}
+
+impl Medal {
+    pub fn sprite(&self) -> &'static str {
+        match self {
+            Medal::Copper => "embedded://sprites/ui/copper-medal.png",
+            Medal::Silver => "embedded://sprites/ui/silver-medal.png",
+            Medal::Gold => "embedded://sprites/ui/gold-medal.png",
+            Medal::Platinum => "embedded://sprites/ui/platinum-medal.png",
+        }
+    }
+}
+
+const MEDALS_FILE: &str = "medals.ron";
+
+// Minimum score needed for each medal tier
+#[derive(Resource, Debug, Clone, Deserialize)]
+pub struct MedalThresholds {
+    pub copper: u64,
+    pub silver: u64,
+    pub gold: u64,
+    pub platinum: u64,
+}
+
+// Tuned alongside the difficulty curves
+impl Default for MedalThresholds {
+    fn default() -> Self {
+        Self {
+            copper: 10,
+            silver: 20,
+            gold: 30,
+            platinum: 40,
+        }
+    }
+}
+
+impl MedalThresholds {
+    // A medals.ron next to the save files overrides the defaults
+    pub fn load() -> Self {
+        match storage::load::<MedalThresholds>(MEDALS_FILE) {
+            Some(thresholds) if thresholds.climbs() => thresholds,
+            Some(thresholds) => {
+                eprintln!(
+                    "Ignoring medal thresholds {:?}, each tier has to ask for more than the last",
+                    thresholds
+                );
+                Self::default()
+            }
+            None => Self::default(),
+        }
+    }
+
+    pub fn climbs(&self) -> bool {
+        self.copper < self.silver && self.silver < self.gold && self.gold < self.platinum
+    }
+
+    pub fn medal_for(&self, score: u64) -> Option<Medal> {
+        if score >= self.platinum {
+            Some(Medal::Platinum)
+        } else if score >= self.gold {
+            Some(Medal::Gold)
+        } else if score >= self.silver {
+            Some(Medal::Silver)
+        } else if score >= self.copper {
+            Some(Medal::Copper)
+        } else {
+            None
+        }
+    }
+}
+
+const MEDAL_SIZE: f32 = 22.;
+const SPARKLE_SIZE: f32 = 6.;
+// sparkle.png is a row of frames, the sparkle grows and shrinks again
+const SPARKLE_FRAMES: usize = 4;
+const SPARKLE_LAYOUT: Handle<TextureAtlasLayout> =
+    Handle::weak_from_u128(0x5b1c_7e0a_9d3f_4a62_8e15_c0d4_7f29_a3b6);
+
+#[derive(Debug, Component)]
+pub struct Sparkle;
+
+// Places the medal over the empty slot on the scoreboard panel
+pub fn spawn_medal(parent: &mut ChildBuilder, asset_server: &AssetServer, medal: Medal) {
+    parent
+        .spawn(ImageBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(12. * 3.),
+                top: Val::Px(21. * 3.),
+                width: Val::Px(MEDAL_SIZE * 3.),
+                height: Val::Px(MEDAL_SIZE * 3.),
+                ..default()
+            },
+            image: UiImage::new(asset_server.load(medal.sprite())),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                ImageBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        width: Val::Px(SPARKLE_SIZE * 3.),
+                        height: Val::Px(SPARKLE_SIZE * 3.),
+                        ..default()
+                    },
+                    image: UiImage::new(asset_server.load("embedded://sprites/ui/sparkle.png")),
+                    ..Default::default()
+                },
+                TextureAtlas {
+                    layout: SPARKLE_LAYOUT,
+                    index: 0,
+                },
+                Sparkle,
+                AnimationIndices {
+                    first: 0,
+                    last: SPARKLE_FRAMES - 1,
+                },
+                AnimationTimer(Timer::new(Duration::from_millis(120), TimerMode::Repeating)),
+            ));
+        });
+}
+
+fn add_sparkle_layout(mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>) {
+    texture_atlas_layouts.insert(
+        SPARKLE_LAYOUT.id(),
+        TextureAtlasLayout::from_grid(
+            UVec2::splat(SPARKLE_SIZE as u32),
+            SPARKLE_FRAMES as u32,
+            1,
+            None,
+            None,
+        ),
+    );
+}
+
+// animate_sprite plays the frames, every cycle the sparkle jumps somewhere new on the medal
+fn move_sparkle(
+    mut query: Query<
+        (&AnimationIndices, &TextureAtlas, &mut Style),
+        (With<Sparkle>, Changed<TextureAtlas>),
+    >,
+) {
+    for (indices, atlas, mut style) in &mut query {
+        if atlas.index != indices.first {
+            continue;
+        }
+        let mut rng = rand::thread_rng();
+        let max_offset = MEDAL_SIZE - SPARKLE_SIZE;
+        style.left = Val::Px(rng.gen_range(0. ..max_offset) * 3.);
+        style.top = Val::Px(rng.gen_range(0. ..max_offset) * 3.);
+    }
+}
+
+pub struct MedalPlugin;
+
+impl Plugin for MedalPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(MedalThresholds::load())
+            .add_systems(Startup, add_sparkle_layout)
+            .add_systems(
+                Update,
+                move_sparkle.run_if(in_state(FlappybirdState::GameOver)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_thresholds_climb() {
+        assert!(MedalThresholds::default().climbs());
+        assert!(!MedalThresholds {
+            copper: 10,
+            silver: 30,
+            gold: 20,
+            platinum: 40,
+        }
+        .climbs());
+    }
+
+    #[test]
+    fn each_threshold_earns_its_medal() {
+        let thresholds = MedalThresholds::default();
+        assert_eq!(thresholds.medal_for(thresholds.copper - 1), None);
+        assert_eq!(thresholds.medal_for(thresholds.copper), Some(Medal::Copper));
+        assert_eq!(thresholds.medal_for(thresholds.silver), Some(Medal::Silver));
+        assert_eq!(thresholds.medal_for(thresholds.gold), Some(Medal::Gold));
+        assert_eq!(
+            thresholds.medal_for(thresholds.platinum),
+            Some(Medal::Platinum)
+        );
+        assert_eq!(thresholds.medal_for(u64::MAX), Some(Medal::Platinum));
+    }
+}
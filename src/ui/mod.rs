@@ -24,6 +24,8 @@ SOFTWARE.
 
 pub mod gameover;
 pub mod leaderboard;
+pub mod medal;
+pub mod pause;
 pub mod score;
 pub mod settings;
 pub mod taptap;
@@ -0,0 +1,261 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use bevy::{prelude::*, window::WindowFocused};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    modes::demo::DemoRun,
+    player::reset_player,
+    sfx::{PlaySfx, Sfx},
+    world::pipes::clear_pipes,
+    FlappybirdState,
+};
+
+use super::titlescreen::spawn_text_button;
+
+const RESUME_COUNTDOWN: f32 = 3.; // seconds
+
+#[derive(Component)]
+pub struct PauseUI;
+
+#[derive(Component)]
+struct CountdownText;
+
+#[derive(Debug, Component, Clone, Copy)]
+pub enum PauseButtons {
+    Resume,
+    Quit,
+}
+
+// Works the Resume button for anything that can't click it, like a control client
+#[derive(Event)]
+pub struct Resume;
+
+// Counts down to the bird flying again, the game stays paused until it runs out
+#[derive(Resource)]
+struct ResumeCountdown(Timer);
+
+fn spawn_pause_overlay(commands: &mut Commands, asset_server: &AssetServer) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(20.0),
+                ..default()
+            },
+            background_color: Color::srgba(0., 0., 0., 0.4).into(),
+            ..default()
+        })
+        .insert(PauseUI)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "PAUSED",
+                TextStyle {
+                    font: asset_server.load("embedded://fonts/pixel.ttf"),
+                    font_size: 60.0,
+                    ..default()
+                },
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        flex_direction: FlexDirection::Row,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    spawn_text_button(parent, asset_server, "RESUME", PauseButtons::Resume);
+                    spawn_text_button(parent, asset_server, "QUIT", PauseButtons::Quit);
+                });
+        });
+}
+
+fn spawn_countdown(commands: &mut Commands, asset_server: &AssetServer) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(PauseUI)
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    (RESUME_COUNTDOWN as u32).to_string(),
+                    TextStyle {
+                        font: asset_server.load("embedded://fonts/pixel.ttf"),
+                        font_size: 120.0,
+                        ..default()
+                    },
+                ),
+                CountdownText,
+            ));
+        });
+    commands.insert_resource(ResumeCountdown(Timer::from_seconds(
+        RESUME_COUNTDOWN,
+        TimerMode::Once,
+    )));
+}
+
+fn despawn_pause_ui(commands: &mut Commands, pause_query: &Query<Entity, With<PauseUI>>) {
+    for entity in pause_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn pause_on_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut focus_events: EventReader<WindowFocused>,
+    mut state: ResMut<NextState<FlappybirdState>>,
+) {
+    let lost_focus = focus_events.read().any(|event| !event.focused);
+    if lost_focus || keyboard_input.any_just_pressed([KeyCode::Escape, KeyCode::KeyP]) {
+        state.set(FlappybirdState::Paused);
+    }
+}
+
+fn enter_pause(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    rapier_config.physics_pipeline_active = false;
+    spawn_pause_overlay(&mut commands, &asset_server);
+}
+
+// Escape or P again works like the Resume button
+fn pause_menu_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    interaction_query: Query<(&Interaction, &PauseButtons), Changed<Interaction>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    pause_query: Query<Entity, With<PauseUI>>,
+    mut resume: EventReader<Resume>,
+    mut sfx: EventWriter<PlaySfx>,
+    mut state: ResMut<NextState<FlappybirdState>>,
+) {
+    let mut pressed = interaction_query
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, button)| *button)
+        .collect::<Vec<_>>();
+    if keyboard_input.any_just_pressed([KeyCode::Escape, KeyCode::KeyP])
+        || resume.read().count() > 0
+    {
+        pressed.push(PauseButtons::Resume);
+    }
+    let Some(button) = pressed.first() else {
+        return;
+    };
+    sfx.send(PlaySfx(Sfx::Swooshing));
+    match button {
+        PauseButtons::Resume => {
+            println!("Resume clicked!");
+            despawn_pause_ui(&mut commands, &pause_query);
+            spawn_countdown(&mut commands, &asset_server);
+        }
+        PauseButtons::Quit => {
+            println!("Quit clicked!");
+            state.set(FlappybirdState::MainTitle);
+        }
+    }
+}
+
+fn resume_countdown(
+    time: Res<Time>,
+    mut countdown: ResMut<ResumeCountdown>,
+    mut text_query: Query<&mut Text, With<CountdownText>>,
+    mut state: ResMut<NextState<FlappybirdState>>,
+) {
+    countdown.0.tick(time.delta());
+    let left = countdown.0.remaining_secs().ceil() as u32;
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = left.to_string();
+    }
+    if countdown.0.finished() {
+        state.set(FlappybirdState::InGame);
+    }
+}
+
+fn exit_pause(
+    mut commands: Commands,
+    pause_query: Query<Entity, With<PauseUI>>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    despawn_pause_ui(&mut commands, &pause_query);
+    commands.remove_resource::<ResumeCountdown>();
+    rapier_config.physics_pipeline_active = true;
+}
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Resume>()
+            .add_systems(OnEnter(FlappybirdState::Paused), enter_pause)
+            .add_systems(OnExit(FlappybirdState::Paused), exit_pause)
+            // quitting mid-run skips the game over screen that normally clears the course
+            .add_systems(
+                OnTransition {
+                    exited: FlappybirdState::Paused,
+                    entered: FlappybirdState::MainTitle,
+                },
+                (reset_player, clear_pipes),
+            )
+            .add_systems(
+                OnTransition {
+                    exited: FlappybirdState::Paused,
+                    entered: FlappybirdState::TapTap,
+                },
+                (reset_player, clear_pipes),
+            )
+            .add_systems(
+                Update,
+                pause_on_input.run_if(
+                    in_state(FlappybirdState::InGame).and_then(not(resource_exists::<DemoRun>)),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    pause_menu_system.run_if(not(resource_exists::<ResumeCountdown>)),
+                    resume_countdown.run_if(resource_exists::<ResumeCountdown>),
+                )
+                    .run_if(in_state(FlappybirdState::Paused)),
+            );
+    }
+}
@@ -24,14 +24,27 @@ SOFTWARE.
 
 use bevy::prelude::*;
 
+use crate::player::Seat;
+
 #[derive(Debug, Component)]
 pub struct ScoreUI;
 
 pub fn spawn_score_ui(commands: &mut Commands, asset_server: &AssetServer) {
+    spawn_seat_score_ui(commands, asset_server, Seat(0), Val::Auto);
+}
+
+// One readout per bird, versus pushes them off to either side with `left`
+pub fn spawn_seat_score_ui(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    seat: Seat,
+    left: Val,
+) {
     let shared_style = Style {
         width: Val::Percent(100.0),
         height: Val::Percent(100.0),
         top: Val::Percent(25.0),
+        left,
         flex_direction: FlexDirection::Column,
         align_items: AlignItems::Center,
         justify_content: JustifyContent::Center,
@@ -52,6 +65,7 @@ pub fn spawn_score_ui(commands: &mut Commands, asset_server: &AssetServer) {
             ..default()
         },
         ScoreUI,
+        seat,
     ));
     commands.spawn((
         TextBundle {
@@ -69,5 +83,6 @@ pub fn spawn_score_ui(commands: &mut Commands, asset_server: &AssetServer) {
             ..default()
         },
         ScoreUI,
+        seat,
     ));
 }
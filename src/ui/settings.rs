@@ -21,3 +21,360 @@ LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
 OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
+
+use bevy::prelude::*;
+use bevy_kira_audio::{Audio, AudioApp, AudioChannel, AudioControl};
+use bevy_rapier2d::render::DebugRenderContext;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    sfx::{PlaySfx, Sfx},
+    storage,
+    world::{difficulty::DifficultyPreset, theme::ThemePreference},
+    FlappybirdState,
+};
+
+const SETTINGS_FILE: &str = "settings.ron";
+
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub sound_effects: bool,
+    pub music: bool,
+    pub theme: ThemePreference,
+    // day turns to night (and back) during long runs
+    pub theme_cycle: bool,
+    pub debug_colliders: bool,
+    // the name from a skin manifest, random_skin picks a new one every run
+    pub skin: String,
+    pub random_skin: bool,
+    pub difficulty: DifficultyPreset,
+    // play the same course every run, overridden by --seed
+    pub seed: Option<u64>,
+    pub daily_attempts: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            sound_effects: true,
+            music: true,
+            theme: ThemePreference::Day,
+            theme_cycle: true,
+            debug_colliders: cfg!(debug_assertions),
+            skin: "yellow".to_string(),
+            random_skin: false,
+            difficulty: DifficultyPreset::Normal,
+            seed: None,
+            daily_attempts: 3,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        storage::load(SETTINGS_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        storage::save(SETTINGS_FILE, self);
+    }
+}
+
+// Audio channel for background music, sound effects stay on the main channel
+#[derive(Resource)]
+pub struct Music;
+
+#[derive(Component)]
+pub struct SettingsUI;
+
+#[derive(Component)]
+pub struct SettingsBackButton;
+
+#[derive(Debug, Component, Clone, Copy)]
+pub enum SettingsToggle {
+    SoundEffects,
+    Music,
+    DebugColliders,
+}
+
+impl SettingsToggle {
+    const ALL: [SettingsToggle; 3] = [
+        SettingsToggle::SoundEffects,
+        SettingsToggle::Music,
+        SettingsToggle::DebugColliders,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            SettingsToggle::SoundEffects => "SOUND",
+            SettingsToggle::Music => "MUSIC",
+            SettingsToggle::DebugColliders => "COLLIDERS",
+        }
+    }
+
+    fn value(&self, settings: &Settings) -> bool {
+        match self {
+            SettingsToggle::SoundEffects => settings.sound_effects,
+            SettingsToggle::Music => settings.music,
+            SettingsToggle::DebugColliders => settings.debug_colliders,
+        }
+    }
+
+    fn value_mut<'a>(&self, settings: &'a mut Settings) -> &'a mut bool {
+        match self {
+            SettingsToggle::SoundEffects => &mut settings.sound_effects,
+            SettingsToggle::Music => &mut settings.music,
+            SettingsToggle::DebugColliders => &mut settings.debug_colliders,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ToggleKnob;
+
+// Cycles through the sky preferences, the last row of the panel
+#[derive(Component)]
+pub struct ThemeButton;
+
+// The panel art has a row every 18px, starting 23px down
+const ROW_TOPS: [f32; 4] = [23., 41., 59., 77.];
+const PANEL_COLOR: Color = Color::srgb(221. / 255., 217. / 255., 156. / 255.);
+const LABEL_COLOR: Color = Color::srgb(252. / 255., 120. / 255., 88. / 255.);
+
+fn knob_left(enabled: bool) -> Val {
+    Val::Px(if enabled { 9. * 3. } else { 1. * 3. })
+}
+
+// Paint over the label baked into the panel art with our own
+fn spawn_row_label(parent: &mut ChildBuilder, asset_server: &AssetServer, label: &str, top: f32) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(20. * 3.),
+                top: Val::Px((top - 1.) * 3.),
+                width: Val::Px(52. * 3.),
+                height: Val::Px(14. * 3.),
+                ..default()
+            },
+            background_color: PANEL_COLOR.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font: asset_server.load("embedded://fonts/pixel.ttf"),
+                    font_size: 24.0,
+                    color: LABEL_COLOR,
+                },
+            ));
+        });
+}
+
+pub fn spawn_settings_screen(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    settings: &Settings,
+) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(SettingsUI)
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(20.0),
+                        left: Val::Px(20.0),
+                        width: Val::Px(7. * 3.),
+                        height: Val::Px(10. * 3.),
+                        ..default()
+                    },
+                    image: UiImage::new(asset_server.load("embedded://sprites/ui/back.png")),
+                    ..Default::default()
+                })
+                .insert(SettingsBackButton);
+
+            parent
+                .spawn(ImageBundle {
+                    style: Style {
+                        width: Val::Px(115. * 3.),
+                        height: Val::Px(110. * 3.),
+                        ..default()
+                    },
+                    image: UiImage::new(
+                        asset_server.load("embedded://sprites/ui/settings-panel.png"),
+                    ),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    for (toggle, top) in SettingsToggle::ALL.iter().zip(ROW_TOPS) {
+                        spawn_row_label(parent, asset_server, toggle.label(), top);
+
+                        parent
+                            .spawn(ButtonBundle {
+                                style: Style {
+                                    position_type: PositionType::Absolute,
+                                    left: Val::Px(77. * 3.),
+                                    top: Val::Px((top - 2.) * 3.),
+                                    width: Val::Px(17. * 3.),
+                                    height: Val::Px(9. * 3.),
+                                    ..default()
+                                },
+                                ..default()
+                            })
+                            .insert(*toggle)
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    ImageBundle {
+                                        style: Style {
+                                            position_type: PositionType::Absolute,
+                                            left: knob_left(toggle.value(settings)),
+                                            top: Val::Px(1. * 3.),
+                                            width: Val::Px(7. * 3.),
+                                            height: Val::Px(7. * 3.),
+                                            ..default()
+                                        },
+                                        image: UiImage::new(
+                                            asset_server.load("embedded://sprites/ui/toggle.png"),
+                                        ),
+                                        ..Default::default()
+                                    },
+                                    ToggleKnob,
+                                ));
+                            });
+                    }
+
+                    // The sky has more than two choices, so it gets a label instead of a knob
+                    let top = ROW_TOPS[SettingsToggle::ALL.len()];
+                    spawn_row_label(parent, asset_server, "SKY", top);
+                    parent
+                        .spawn(ButtonBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                left: Val::Px(74. * 3.),
+                                top: Val::Px((top - 1.) * 3.),
+                                height: Val::Px(14. * 3.),
+                                ..default()
+                            },
+                            background_color: PANEL_COLOR.into(),
+                            ..default()
+                        })
+                        .insert(ThemeButton)
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                settings.theme.label(),
+                                TextStyle {
+                                    font: asset_server.load("embedded://fonts/pixel.ttf"),
+                                    font_size: 24.0,
+                                    color: LABEL_COLOR,
+                                },
+                            ));
+                        });
+                });
+        });
+}
+
+fn show_settings_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+) {
+    spawn_settings_screen(&mut commands, &asset_server, &settings);
+}
+
+fn despawn_settings_screen(mut commands: Commands, query: Query<Entity, With<SettingsUI>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn settings_button_system(
+    toggle_query: Query<(&Interaction, &SettingsToggle, &Children), Changed<Interaction>>,
+    back_query: Query<&Interaction, (Changed<Interaction>, With<SettingsBackButton>)>,
+    theme_query: Query<(&Interaction, &Children), (Changed<Interaction>, With<ThemeButton>)>,
+    mut knob_query: Query<&mut Style, With<ToggleKnob>>,
+    mut text_query: Query<&mut Text>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut sfx: EventWriter<PlaySfx>,
+    mut settings: ResMut<Settings>,
+    mut state: ResMut<NextState<FlappybirdState>>,
+) {
+    for (interaction, toggle, children) in toggle_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let value = toggle.value_mut(&mut settings);
+        *value = !*value;
+        let enabled = *value;
+        println!("{:?} set to {}", toggle, enabled);
+        for child in children.iter() {
+            if let Ok(mut style) = knob_query.get_mut(*child) {
+                style.left = knob_left(enabled);
+            }
+        }
+        settings.save();
+    }
+
+    for (interaction, children) in theme_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        settings.theme = settings.theme.next();
+        println!("Sky set to {:?}", settings.theme);
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = settings.theme.label().to_string();
+            }
+        }
+        settings.save();
+    }
+
+    let back_pressed = back_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+    if back_pressed || keyboard_input.just_pressed(KeyCode::Escape) {
+        println!("Settings back clicked!");
+        sfx.send(PlaySfx(Sfx::Swooshing));
+        state.set(FlappybirdState::MainTitle);
+    }
+}
+
+fn apply_settings(
+    settings: Res<Settings>,
+    audio: Res<Audio>,
+    music: Res<AudioChannel<Music>>,
+    mut debug_render: ResMut<DebugRenderContext>,
+) {
+    audio.set_volume(if settings.sound_effects { 1.0 } else { 0.0 });
+    music.set_volume(if settings.music { 1.0 } else { 0.0 });
+    debug_render.enabled = settings.debug_colliders;
+}
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Settings::load())
+            .add_audio_channel::<Music>()
+            .add_systems(OnEnter(FlappybirdState::Settings), show_settings_screen)
+            .add_systems(OnExit(FlappybirdState::Settings), despawn_settings_screen)
+            .add_systems(
+                Update,
+                settings_button_system.run_if(in_state(FlappybirdState::Settings)),
+            )
+            .add_systems(Update, apply_settings.run_if(resource_changed::<Settings>));
+    }
+}
@@ -22,14 +22,27 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
 
-use bevy::{prelude::*, window::PrimaryWindow};
-use bevy_kira_audio::{Audio, AudioControl};
+use bevy::prelude::*;
 use strum::EnumIter;
 
 use crate::{
-    player::Player,
-    ui::{score::spawn_score_ui, taptap::spawn_taptap_screen},
-    FlappybirdState,
+    modes::{
+        daily::{today, DailyRecords},
+        demo::start_demo,
+        GameMode,
+    },
+    player::{
+        controller::InputSource,
+        skin::{next_skin, skin_label, SkinRegistry},
+        Player,
+    },
+    sfx::{PlaySfx, Sfx},
+    ui::{
+        score::{spawn_score_ui, ScoreUI},
+        settings::Settings,
+        taptap::spawn_taptap_screen,
+    },
+    FlappybirdState, Playfield,
 };
 
 #[derive(Component)]
@@ -42,9 +55,57 @@ pub enum TitleScreenButtons {
     Settings,
     Scoreboard,
     Exit,
+    Daily,
+    Demo,
+    Evolve,
+    Versus,
+    Skin,
+    Difficulty,
+    Levels,
 }
 
-pub fn spawn_title(commands: &mut Commands, asset_server: &AssetServer) {
+// Game modes have no button art, so they get a plain labelled button
+pub fn spawn_text_button(
+    commands: &mut ChildBuilder,
+    asset_server: &AssetServer,
+    label: &str,
+    button: impl Component,
+) {
+    commands
+        .spawn(ButtonBundle {
+            style: Style {
+                height: Val::Px(14. * 3.),
+                padding: UiRect::horizontal(Val::Px(12.0)),
+                margin: UiRect::horizontal(Val::Px(10.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                border: UiRect::all(Val::Px(3.0)),
+                ..default()
+            },
+            background_color: Color::srgb(0.98, 0.47, 0.35).into(),
+            border_color: Color::WHITE.into(),
+            ..Default::default()
+        })
+        .insert(button)
+        .with_children(|commands| {
+            commands.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font: asset_server.load("embedded://fonts/pixel.ttf"),
+                    font_size: 24.0,
+                    ..default()
+                },
+            ));
+        });
+}
+
+pub fn spawn_title(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    daily_attempts_left: u32,
+    skin_label: &str,
+    difficulty_label: &str,
+) {
     println!("Title spawned");
     commands
         .spawn(NodeBundle {
@@ -144,11 +205,126 @@ pub fn spawn_title(commands: &mut Commands, asset_server: &AssetServer) {
                         })
                         .insert(TitleScreenButtons::Settings);
                 });
+
+            // Game modes node
+            commands
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Auto,
+                        margin: UiRect::top(Val::Px(20.0)),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        flex_direction: FlexDirection::Row,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|commands| {
+                    spawn_text_button(
+                        commands,
+                        asset_server,
+                        &format!("DAILY {} LEFT", daily_attempts_left),
+                        TitleScreenButtons::Daily,
+                    );
+                    spawn_text_button(commands, asset_server, "DEMO", TitleScreenButtons::Demo);
+                    spawn_text_button(commands, asset_server, "EVOLVE", TitleScreenButtons::Evolve);
+                    spawn_text_button(commands, asset_server, "VERSUS", TitleScreenButtons::Versus);
+                    spawn_text_button(commands, asset_server, "LEVELS", TitleScreenButtons::Levels);
+                });
+
+            // Skin and difficulty pickers, the bird in the middle of the screen shows the skin
+            commands
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Auto,
+                        margin: UiRect::top(Val::Px(20.0)),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|commands| {
+                    spawn_text_button(commands, asset_server, skin_label, TitleScreenButtons::Skin);
+                    spawn_text_button(
+                        commands,
+                        asset_server,
+                        difficulty_label,
+                        TitleScreenButtons::Difficulty,
+                    );
+                });
         });
 }
 
-pub fn spawn_main_title_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
-    spawn_title(&mut commands, &asset_server);
+pub fn spawn_main_title_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    daily: Res<DailyRecords>,
+    settings: Res<Settings>,
+) {
+    spawn_title(
+        &mut commands,
+        &asset_server,
+        daily.attempts_left(today(), &settings),
+        &skin_label(&settings),
+        settings.difficulty.label(),
+    );
+}
+
+pub fn start_run(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    state: &mut NextState<FlappybirdState>,
+    transform: &mut Transform,
+    playfield: &Playfield,
+) {
+    // set the state to TapTap, the title screen despawns itself on exit
+    transform.translation = Vec3::new(playfield.width / 6., playfield.height / 2., 2.); // set player to the gameplay area
+    spawn_score_ui(commands, asset_server);
+    spawn_taptap_screen(commands, asset_server);
+    state.set(FlappybirdState::TapTap);
+}
+
+fn despawn_title_screen(mut commands: Commands, query: Query<Entity, With<TitleScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// Coming back from a run, the score goes away and the bird returns to the middle
+pub fn reset_title_scene(
+    mut commands: Commands,
+    score_query: Query<Entity, With<ScoreUI>>,
+    mut player_query: Query<&mut Transform, With<Player>>,
+    playfield: Res<Playfield>,
+) {
+    for entity in score_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    if let Ok(mut transform) = player_query.get_single_mut() {
+        transform.translation = Vec3::new(playfield.width / 2., playfield.height / 2. + 70., 2.);
+    }
+}
+
+fn update_picker_buttons(
+    settings: Res<Settings>,
+    button_query: Query<(&TitleScreenButtons, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (button, children) in button_query.iter() {
+        let label = match button {
+            TitleScreenButtons::Skin => skin_label(&settings),
+            TitleScreenButtons::Difficulty => settings.difficulty.label().to_string(),
+            _ => continue,
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = label.clone();
+            }
+        }
+    }
 }
 
 #[derive(Debug, Component, Default)]
@@ -158,38 +334,92 @@ fn title_button_system(
     mut commands: Commands,
     mut interaction_query: Query<(&Interaction, &TitleScreenButtons), Changed<Interaction>>,
     asset_server: Res<AssetServer>,
-    audio: Res<Audio>,
+    mut sfx: EventWriter<PlaySfx>,
     mut state: ResMut<NextState<FlappybirdState>>,
     mut player_query: Query<&mut Transform, With<Player>>,
-    title_screen: Query<Entity, With<TitleScreen>>,
-    window_query: Query<&Window, With<PrimaryWindow>>,
+    playfield: Res<Playfield>,
+    mut mode: ResMut<GameMode>,
+    mut input_source: ResMut<InputSource>,
+    daily: Res<DailyRecords>,
+    mut settings: ResMut<Settings>,
+    registry: Res<SkinRegistry>,
 ) {
     for (interaction, button) in interaction_query.iter_mut() {
-        let window = window_query.single();
         let mut transform = player_query.single_mut();
         println!("{:?}", button);
         match *interaction {
             Interaction::Pressed => {
                 // Handle button click
-                audio.play(
-                    asset_server
-                        .get_handle("embedded://audio/sfx_swooshing.ogg")
-                        .unwrap_or(asset_server.load("embedded://audio/sfx_swooshing.ogg")),
-                );
+                sfx.send(PlaySfx(Sfx::Swooshing));
                 match button {
                     TitleScreenButtons::Play => {
                         println!("Play button clicked!");
-                        // Destroy the title screen, spawn tap tap screen
-                        commands
-                            .get_entity(title_screen.single())
-                            .unwrap()
-                            .despawn_recursive();
-                        // set the state to TapTap
-                        transform.translation =
-                            Vec3::new(window.width() / 6., window.height() / 2., 2.); // set player to the gameplay area
-                        spawn_score_ui(&mut commands, &asset_server);
-                        spawn_taptap_screen(&mut commands, &asset_server);
-                        state.set(FlappybirdState::TapTap);
+                        *mode = GameMode::Endless;
+                        start_run(
+                            &mut commands,
+                            &asset_server,
+                            &mut state,
+                            &mut transform,
+                            &playfield,
+                        );
+                    }
+                    TitleScreenButtons::Daily => {
+                        println!("Daily button clicked!");
+                        if daily.attempts_left(today(), &settings) == 0 {
+                            println!("No daily attempts left today");
+                            continue;
+                        }
+                        *mode = GameMode::Daily;
+                        start_run(
+                            &mut commands,
+                            &asset_server,
+                            &mut state,
+                            &mut transform,
+                            &playfield,
+                        );
+                    }
+                    TitleScreenButtons::Demo => {
+                        println!("Demo button clicked!");
+                        start_demo(
+                            &mut commands,
+                            &asset_server,
+                            &mut state,
+                            &mut input_source,
+                            &mut mode,
+                            &mut transform,
+                            &playfield,
+                        );
+                    }
+                    TitleScreenButtons::Evolve => {
+                        println!("Evolve button clicked!");
+                        // the generations fly the endless course, whatever was played last
+                        *mode = GameMode::Endless;
+                        state.set(FlappybirdState::Evolve);
+                    }
+                    TitleScreenButtons::Versus => {
+                        println!("Versus button clicked!");
+                        *mode = GameMode::Versus;
+                        start_run(
+                            &mut commands,
+                            &asset_server,
+                            &mut state,
+                            &mut transform,
+                            &playfield,
+                        );
+                    }
+                    TitleScreenButtons::Levels => {
+                        println!("Levels button clicked!");
+                        state.set(FlappybirdState::LevelSelect);
+                    }
+                    TitleScreenButtons::Skin => {
+                        next_skin(&mut settings, &registry);
+                        println!("Skin set to {}", skin_label(&settings));
+                        settings.save();
+                    }
+                    TitleScreenButtons::Difficulty => {
+                        settings.difficulty = settings.difficulty.next();
+                        println!("Difficulty set to {}", settings.difficulty.label());
+                        settings.save();
                     }
                     TitleScreenButtons::Github => {
                         println!("Github button clicked!");
@@ -204,6 +434,7 @@ fn title_button_system(
                     }
                     TitleScreenButtons::Scoreboard => {
                         println!("Scoreboard button clicked!");
+                        state.set(FlappybirdState::Scoreboard);
                     }
                 }
             }
@@ -223,10 +454,19 @@ pub struct TitlescreenPlugin;
 
 impl Plugin for TitlescreenPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_main_title_screen)
-            .add_systems(
-                Update,
-                title_button_system.run_if(in_state(FlappybirdState::MainTitle)),
-            );
+        app.add_systems(
+            OnEnter(FlappybirdState::MainTitle),
+            (spawn_main_title_screen, reset_title_scene),
+        )
+        .add_systems(OnExit(FlappybirdState::MainTitle), despawn_title_screen)
+        .add_systems(
+            Update,
+            (
+                title_button_system,
+                update_picker_buttons.run_if(resource_changed::<Settings>),
+            )
+                .chain()
+                .run_if(in_state(FlappybirdState::MainTitle)),
+        );
     }
 }
@@ -0,0 +1,232 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use rand::Rng;
+
+use crate::{
+    player::{
+        controller::{FLAP_SPEED, PLAYER_SPEED},
+        BIRD_BODY, BIRD_SCALE,
+    },
+    timestep::TICK_RATE,
+    PIXELS_PER_METER,
+};
+
+use super::{
+    difficulty::DifficultyStep,
+    pipes::{PIPE_HALF_WIDTH, PIPE_SCALE, PIPE_SPACING},
+};
+
+// Rapier's default gravity, nothing in the game changes it
+pub const GRAVITY: f32 = 9.81 * PIXELS_PER_METER;
+// Gap centers sit a whole number of these off the middle of the playfield
+pub const GAP_STEP: f32 = 40.;
+const PIPE_WIDTH: f32 = PIPE_HALF_WIDTH * 2. * PIPE_SCALE;
+const BIRD_WIDTH: f32 = (BIRD_BODY.half_length + BIRD_BODY.radius) * 2. * BIRD_SCALE;
+
+// One fixed tick of a bird's flight, in the order the controller and the physics step play
+// it out. A flap stops the fall, the limiter caps the speed the bird already had, then the
+// physics step adds the flap's impulse and gravity for the whole tick
+pub fn fly(y: f32, vy: f32, flap: bool) -> (f32, f32) {
+    let dt = 1. / TICK_RATE as f32;
+    let vy = if flap { vy.max(0.) } else { vy };
+    let vy = vy.min(PLAYER_SPEED) + if flap { FLAP_SPEED } else { 0. } - GRAVITY * dt;
+    (y + vy * dt, vy)
+}
+
+// How far up and down a bird flying level out of one gap can get before it reaches the
+// next one. Flapping every tick is the fastest climb and not flapping at all the fastest drop
+pub fn reach(speed: f32) -> (f32, f32) {
+    let time = (PIPE_SPACING - PIPE_WIDTH - BIRD_WIDTH).max(0.) / speed;
+    let ticks = (time * TICK_RATE as f32).floor() as u32;
+    let (mut rise, mut climb) = (0., 0.);
+    let (mut drop, mut fall) = (0., 0.);
+    for _ in 0..ticks {
+        (rise, climb) = fly(rise, climb, true);
+        (drop, fall) = fly(drop, fall, false);
+    }
+    (rise, -drop)
+}
+
+// Picks the next gap, in steps off the middle, among the ones the bird can make from
+// `previous`. fairness is the share of the reach held back for a human's reactions
+pub fn next_gap(rng: &mut impl Rng, previous: i32, step: &DifficultyStep, fairness: f32) -> i32 {
+    let (low, high) = step.gap_steps;
+    let (rise, drop) = reach(step.speed);
+    let kept = 1. - fairness.clamp(0., 1.);
+    let max_up = (rise * kept / GAP_STEP).floor() as i32;
+    let max_down = (drop * kept / GAP_STEP).floor() as i32;
+
+    let from = low.max(previous - max_down);
+    let to = (high - 1).min(previous + max_up);
+    if from > to {
+        // the range moved away from the last gap, head back in as close as it gets
+        return if previous < low { low } else { high - 1 };
+    }
+    rng.gen_range(from..=to)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use bevy_rapier2d::prelude::Velocity;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::{
+        cli::Args,
+        headless::headless_app,
+        player::{
+            controller::{FlapIntent, InputSource},
+            Player,
+        },
+        world::difficulty::DifficultyPreset,
+        FlappybirdState,
+    };
+
+    fn step(speed: f32, gap_steps: (i32, i32)) -> DifficultyStep {
+        DifficultyStep {
+            score: 0,
+            speed,
+            gap: 80.,
+            gap_steps,
+        }
+    }
+
+    // The bird's height and climb at the end of every fixed tick
+    #[derive(Resource, Default)]
+    struct Flight(Vec<(f32, f32)>);
+
+    fn record_flight(
+        mut flight: ResMut<Flight>,
+        player_query: Query<(&Transform, &Velocity), With<Player>>,
+    ) {
+        for (transform, velocity) in player_query.iter() {
+            flight.0.push((transform.translation.y, velocity.linvel.y));
+        }
+    }
+
+    fn set_state(app: &mut App, state: FlappybirdState) {
+        app.world_mut()
+            .resource_mut::<NextState<FlappybirdState>>()
+            .set(state);
+        app.update();
+    }
+
+    // Flaps a real bird in the headless game, two ticks in a row to run into the speed
+    // limit, and steps `fly` along from where the first flap left it
+    #[test]
+    fn fly_follows_a_headless_flap() {
+        const TICKS: usize = 40;
+        let flaps = [0, 12, 13, 28];
+
+        let mut app = headless_app(Args {
+            seed: Some(1),
+            ..default()
+        });
+        app.insert_resource(InputSource::Agent)
+            .init_resource::<Flight>()
+            .add_systems(FixedLast, record_flight);
+        app.update();
+        // going through game over is what puts the bird and pipes in place
+        set_state(&mut app, FlappybirdState::GameOver);
+        set_state(&mut app, FlappybirdState::TapTap);
+        app.world_mut().resource_mut::<Flight>().0.clear();
+
+        for tick in 0..TICKS {
+            if flaps.contains(&tick) {
+                let world = app.world_mut();
+                world
+                    .query_filtered::<&mut FlapIntent, With<Player>>()
+                    .single_mut(world)
+                    .0 = true;
+            }
+            app.update();
+        }
+        assert_eq!(
+            app.world().resource::<State<FlappybirdState>>().get(),
+            &FlappybirdState::InGame
+        );
+
+        let flight = &app.world().resource::<Flight>().0;
+        assert_eq!(flight.len(), TICKS);
+        // the first flap is also the one that lets the bird go, so start after it
+        let (mut y, mut vy) = flight[0];
+        for (tick, &(real_y, real_vy)) in flight.iter().enumerate().skip(1) {
+            (y, vy) = fly(y, vy, flaps.contains(&tick));
+            assert!(
+                (y - real_y).abs() < 0.01 && (vy - real_vy).abs() < 0.01,
+                "tick {tick}: fly ({y}, {vy}), game ({real_y}, {real_vy})"
+            );
+        }
+    }
+
+    #[test]
+    fn gaps_stay_within_the_step() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        // slow enough that the whole range is in reach
+        let step = step(40., (-2, 3));
+        for previous in -2..3 {
+            for _ in 0..100 {
+                let gap = next_gap(&mut rng, previous, &step, 0.);
+                assert!((-2..3).contains(&gap), "{gap} from {previous}");
+            }
+        }
+    }
+
+    #[test]
+    fn gaps_out_of_range_head_back_to_the_nearest_edge() {
+        let mut rng = ChaCha8Rng::seed_from_u64(2);
+        let step = step(200., (-2, 3));
+        assert_eq!(next_gap(&mut rng, 20, &step, 0.), 2);
+        assert_eq!(next_gap(&mut rng, -20, &step, 0.), -2);
+    }
+
+    #[test]
+    fn gaps_are_reachable_at_each_presets_top_speed() {
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        for preset in [
+            DifficultyPreset::Easy,
+            DifficultyPreset::Normal,
+            DifficultyPreset::Hard,
+        ] {
+            let curve = preset.curve();
+            let top = *curve.steps.last().unwrap();
+            let (rise, drop) = reach(top.speed);
+            let kept = 1. - curve.fairness;
+            let (low, high) = top.gap_steps;
+            for previous in low..high {
+                for _ in 0..100 {
+                    let gap = next_gap(&mut rng, previous, &top, curve.fairness);
+                    let moved = (gap - previous) as f32 * GAP_STEP;
+                    assert!(
+                        moved <= rise * kept && -moved <= drop * kept,
+                        "{preset:?} moved {moved} from {previous}"
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,312 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::Args, modes::GameMode, player::Player, replay::ReplayPlayback, timestep::GameplaySet,
+    ui::settings::Settings, FlappybirdState,
+};
+
+use super::{advance_scroll, seed_world_rng, WorldSpeed};
+
+// Curves shipped with the game, one per preset
+const EASY_CURVE: &str = include_str!("../../assets/difficulty/easy.ron");
+const NORMAL_CURVE: &str = include_str!("../../assets/difficulty/normal.ron");
+const HARD_CURVE: &str = include_str!("../../assets/difficulty/hard.ron");
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DifficultyPreset {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl DifficultyPreset {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DifficultyPreset::Easy => "EASY",
+            DifficultyPreset::Normal => "NORMAL",
+            DifficultyPreset::Hard => "HARD",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            DifficultyPreset::Easy => DifficultyPreset::Normal,
+            DifficultyPreset::Normal => DifficultyPreset::Hard,
+            DifficultyPreset::Hard => DifficultyPreset::Easy,
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "easy" => Some(DifficultyPreset::Easy),
+            "normal" => Some(DifficultyPreset::Normal),
+            "hard" => Some(DifficultyPreset::Hard),
+            _ => None,
+        }
+    }
+
+    // How replays store the preset
+    pub fn to_byte(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(DifficultyPreset::Easy),
+            1 => Some(DifficultyPreset::Normal),
+            2 => Some(DifficultyPreset::Hard),
+            _ => None,
+        }
+    }
+
+    pub fn curve(&self) -> DifficultyCurve {
+        let source = match self {
+            DifficultyPreset::Easy => EASY_CURVE,
+            DifficultyPreset::Normal => NORMAL_CURVE,
+            DifficultyPreset::Hard => HARD_CURVE,
+        };
+        ron::from_str(source).expect("built-in difficulty curve should parse")
+    }
+}
+
+// How the course plays from `score` on, gap is the opening between two pipes in sprite
+// pixels and gap_steps the range of GAP_STEP steps the opening is moved off the middle
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct DifficultyStep {
+    pub score: u64,
+    pub speed: f32,
+    pub gap: f32,
+    pub gap_steps: (i32, i32),
+}
+
+// A difficulty/<preset>.ron file. Speed and gap blend from one step to the next as the
+// score rises, the gap heights change when a step is reached
+#[derive(Debug, Clone, Deserialize)]
+pub struct DifficultyCurve {
+    // 0 lets a gap go as far as a perfect bird can fly, 1 keeps every gap where the last was
+    pub fairness: f32,
+    pub steps: Vec<DifficultyStep>,
+}
+
+impl DifficultyCurve {
+    // Speed and gap blend towards the next step, the gap heights switch when it is reached
+    pub fn at(&self, score: u64) -> DifficultyStep {
+        let next = self
+            .steps
+            .iter()
+            .position(|step| step.score > score)
+            .unwrap_or(self.steps.len());
+        let Some(from) = next.checked_sub(1).map(|index| self.steps[index]) else {
+            return self.steps[0];
+        };
+        let Some(to) = self.steps.get(next) else {
+            return DifficultyStep { score, ..from };
+        };
+        let t = (score - from.score) as f32 / (to.score - from.score) as f32;
+        DifficultyStep {
+            score,
+            speed: from.speed + (to.speed - from.speed) * t,
+            gap: from.gap + (to.gap - from.gap) * t,
+            gap_steps: from.gap_steps,
+        }
+    }
+
+    pub fn start(&self) -> DifficultyStep {
+        self.at(0)
+    }
+}
+
+// The preset of the current run and where on its curve the run is
+#[derive(Resource, Debug, Clone)]
+pub struct Difficulty {
+    pub preset: DifficultyPreset,
+    pub curve: DifficultyCurve,
+    pub current: DifficultyStep,
+}
+
+impl Difficulty {
+    pub fn new(preset: DifficultyPreset) -> Self {
+        let curve = preset.curve();
+        Self {
+            preset,
+            current: curve.start(),
+            curve,
+        }
+    }
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Self::new(DifficultyPreset::default())
+    }
+}
+
+// Replays bring their own preset and everyone plays the daily course on normal,
+// otherwise --difficulty or the settings pick it
+pub fn reset_difficulty(
+    mut difficulty: ResMut<Difficulty>,
+    mut speed: ResMut<WorldSpeed>,
+    args: Res<Args>,
+    settings: Res<Settings>,
+    mode: Res<GameMode>,
+    playback: Option<Res<ReplayPlayback>>,
+) {
+    let preset = match (playback, *mode) {
+        (Some(playback), _) => playback.replay.difficulty,
+        (None, GameMode::Daily) => DifficultyPreset::Normal,
+        (None, _) => args.difficulty.unwrap_or(settings.difficulty),
+    };
+    if preset != difficulty.preset {
+        println!("Difficulty: {}", preset.label());
+    }
+    *difficulty = Difficulty::new(preset);
+    speed.0 = difficulty.current.speed;
+}
+
+// The best bird sets the pace, in versus both share the course
+fn ramp_difficulty(
+    player_query: Query<&Player>,
+    mut difficulty: ResMut<Difficulty>,
+    mut speed: ResMut<WorldSpeed>,
+) {
+    let score = player_query
+        .iter()
+        .map(|player| player.score)
+        .max()
+        .unwrap_or(0);
+    if score == difficulty.current.score {
+        return;
+    }
+    difficulty.current = difficulty.curve.at(score);
+    speed.0 = difficulty.current.speed;
+}
+
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Difficulty>()
+            .add_systems(
+                OnEnter(FlappybirdState::TapTap),
+                reset_difficulty.before(seed_world_rng),
+            )
+            // the title and the evolution run at the starting pace
+            .add_systems(OnEnter(FlappybirdState::MainTitle), reset_difficulty)
+            .add_systems(OnEnter(FlappybirdState::Evolve), reset_difficulty)
+            // the title's picker changes the settings, the scoreboard shows the picked preset
+            .add_systems(
+                Update,
+                reset_difficulty.run_if(
+                    in_state(FlappybirdState::MainTitle).and_then(resource_changed::<Settings>),
+                ),
+            )
+            .add_systems(
+                FixedUpdate,
+                ramp_difficulty
+                    .in_set(GameplaySet::Simulation)
+                    .before(advance_scroll)
+                    .run_if(in_state(FlappybirdState::InGame))
+                    // a level plays at the speed it was designed for
+                    .run_if(not(resource_equals(GameMode::Level))),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve() -> DifficultyCurve {
+        DifficultyCurve {
+            fairness: 0.25,
+            steps: vec![
+                DifficultyStep {
+                    score: 0,
+                    speed: 100.,
+                    gap: 80.,
+                    gap_steps: (-2, 3),
+                },
+                DifficultyStep {
+                    score: 10,
+                    speed: 200.,
+                    gap: 60.,
+                    gap_steps: (-4, 5),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn starts_on_the_first_step() {
+        assert_eq!(curve().start(), curve().steps[0]);
+    }
+
+    #[test]
+    fn blends_between_steps() {
+        let step = curve().at(5);
+        assert_eq!(step.score, 5);
+        assert_eq!(step.speed, 150.);
+        assert_eq!(step.gap, 70.);
+        // the heights only change once the next step is reached
+        assert_eq!(step.gap_steps, (-2, 3));
+        assert_eq!(curve().at(10).gap_steps, (-4, 5));
+    }
+
+    #[test]
+    fn holds_the_last_step_past_the_end() {
+        let step = curve().at(1000);
+        assert_eq!(step.score, 1000);
+        assert_eq!(step.speed, 200.);
+        assert_eq!(step.gap, 60.);
+        assert_eq!(step.gap_steps, (-4, 5));
+    }
+
+    #[test]
+    fn a_curve_that_starts_late_plays_its_first_step_early() {
+        let mut curve = curve();
+        curve.steps[0].score = 3;
+        assert_eq!(curve.at(0), curve.steps[0]);
+    }
+
+    #[test]
+    fn shipped_curves_parse_and_climb() {
+        for preset in [
+            DifficultyPreset::Easy,
+            DifficultyPreset::Normal,
+            DifficultyPreset::Hard,
+        ] {
+            let curve = preset.curve();
+            assert!(!curve.steps.is_empty());
+            assert!(curve
+                .steps
+                .windows(2)
+                .all(|pair| pair[0].score < pair[1].score));
+        }
+    }
+}
@@ -22,49 +22,74 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
 
-use bevy::{prelude::*, window::PrimaryWindow};
+use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
-use chrono::offset;
 
-use crate::FlappybirdState;
+use crate::{
+    timestep::{GameplaySet, RenderInterpolation},
+    Playfield,
+};
 
-use super::WorldSpeed;
+use super::{advance_scroll, world_scrolling, WorldScroll};
 
 #[derive(Component)]
 pub struct Ground;
 
-#[derive(Resource)]
-struct GroundOffset(pub f32);
+// The land sprite is drawn at three times the size, standing on the bottom of the playfield
+const GROUND_HEIGHT: f32 = 56.;
+const GROUND_SCALE: f32 = 3.;
+// Where the ground's collider ends, its center is half the sprite's height up
+pub const GROUND_TOP: f32 = GROUND_HEIGHT / 2. + GROUND_HEIGHT / 2. * GROUND_SCALE;
+
+fn spawn_ground(mut commands: Commands, playfield: Res<Playfield>, asset_server: Res<AssetServer>) {
+    let ground_width = 168.;
+    let ground_texture = asset_server.load("embedded://sprites/world/land.png");
+
+    let ground_scale = Vec3::splat(GROUND_SCALE);
+
+    let number_of_grounds = (playfield.width / (ground_width * ground_scale.x)).ceil() as u32 + 1;
+
+    for i in 0..number_of_grounds {
+        commands.spawn((
+            SpriteBundle {
+                texture: ground_texture.clone(),
+                transform: Transform {
+                    translation: Vec3::new(
+                        i as f32 * (ground_width * ground_scale.x),
+                        GROUND_HEIGHT / 2.,
+                        2.,
+                    ),
+                    scale: ground_scale,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            Ground,
+            Collider::cuboid(168. / 2., GROUND_HEIGHT / 2.),
+            ActiveEvents::COLLISION_EVENTS,
+            RenderInterpolation::default(),
+        ));
+    }
+}
 
-fn move_ground(
-    time: Res<Time>,
-    speed: Res<WorldSpeed>,
-    mut query: Query<&mut Transform, With<Ground>>,
-    mut offset: ResMut<GroundOffset>,
-) {
-    let delta_x = time.delta_seconds() * speed.0;
+fn move_ground(scroll: Res<WorldScroll>, mut query: Query<&mut Transform, With<Ground>>) {
     for mut transform in query.iter_mut() {
-        transform.translation.x -= delta_x;
+        transform.translation.x -= scroll.delta;
     }
-
-    offset.0 += delta_x;
 }
 
 fn despawn_and_spawn_ground(
     mut commands: Commands,
     mut query: Query<(Entity, &Transform), With<Ground>>,
-    window_query: Query<&Window, With<PrimaryWindow>>,
+    playfield: Res<Playfield>,
     asset_server: Res<AssetServer>,
-    mut offset: ResMut<GroundOffset>,
+    scroll: Res<WorldScroll>,
 ) {
-    let window = window_query.get_single().unwrap();
-    let texture_handle = asset_server
-        .get_handle("embedded://sprites/world/land.png")
-        .unwrap();
+    let texture_handle = asset_server.load("embedded://sprites/world/land.png");
     let texture_width = 168.0; // Adjust based on your texture width
-    let texture_scale = Vec3::splat(3.); // Adjust based on your texture scale
+    let texture_scale = Vec3::splat(GROUND_SCALE);
 
-    let effective_width = texture_width * texture_scale.x - offset.0;
+    let effective_width = texture_width * texture_scale.x - scroll.delta;
 
     let mut ground_entities: Vec<(Entity, &Transform)> = query.iter_mut().collect();
     ground_entities.sort_by(|a, b| a.1.translation.x.partial_cmp(&b.1.translation.x).unwrap());
@@ -77,7 +102,7 @@ fn despawn_and_spawn_ground(
             // Spawn new ground entities on the right if needed
             let rightmost_x = ground_entities
                 .last()
-                .map_or(-window.width() / 2., |(_, transform)| {
+                .map_or(-playfield.width / 2., |(_, transform)| {
                     transform.translation.x
                 });
             let new_x = rightmost_x + effective_width;
@@ -85,28 +110,36 @@ fn despawn_and_spawn_ground(
                 SpriteBundle {
                     texture: texture_handle.clone(),
                     transform: Transform {
-                        translation: Vec3::new(new_x, 56. / 2., 2.), // Adjust based on your texture width, scale, and position
+                        translation: Vec3::new(new_x, GROUND_HEIGHT / 2., 2.),
                         scale: texture_scale,
                         ..Default::default()
                     },
                     ..Default::default()
                 },
-                Collider::cuboid(168. / 2., 56. / 2.),
+                Collider::cuboid(168. / 2., GROUND_HEIGHT / 2.),
                 ActiveEvents::COLLISION_EVENTS,
                 Ground,
+                RenderInterpolation::default(),
             ));
         }
     }
-    offset.0 = 0.;
 }
 
 pub struct GroundPlugin;
 impl Plugin for GroundPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(GroundOffset(0.)).add_systems(
-            Update,
-            (move_ground, despawn_and_spawn_ground)
-                .run_if(not(in_state(FlappybirdState::GameOver))),
-        );
+        app.init_resource::<WorldScroll>()
+            .add_systems(Startup, spawn_ground)
+            .add_systems(
+                FixedUpdate,
+                (
+                    advance_scroll,
+                    (move_ground, despawn_and_spawn_ground)
+                        .chain()
+                        .run_if(world_scrolling),
+                )
+                    .chain()
+                    .in_set(GameplaySet::Simulation),
+            );
     }
 }
@@ -23,13 +23,113 @@ SOFTWARE.
 */
 
 use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 
+use crate::{
+    cli::Args,
+    modes::{
+        daily::{daily_seed, today},
+        GameMode,
+    },
+    replay::ReplayPlayback,
+    ui::settings::Settings,
+    FlappybirdState,
+};
+
+pub mod course;
+pub mod difficulty;
 pub mod ground;
 pub mod pipes;
 pub mod sky;
+pub mod theme;
 
-#[derive(Resource)]
-struct SpawnTimer(Timer);
+// Distance scrolled since the last pipe, pipes come every PIPE_SPACING pixels
+// whatever the speed
+#[derive(Resource, Default)]
+pub struct PipeSpawner {
+    pub travelled: f32,
+    // steps off the middle of the last gap, every run starts level with the bird
+    pub last_gap: i32,
+}
 
 #[derive(Resource)]
 pub struct WorldSpeed(pub f32);
+
+// How far the world has scrolled, the ground, the sky and the pipes all move by it
+#[derive(Resource, Default)]
+pub struct WorldScroll {
+    // pixels scrolled this tick
+    pub delta: f32,
+    pub distance: f32,
+}
+
+// The backdrop keeps scrolling on the menus and stops with the bird
+pub fn world_scrolling(state: Res<State<FlappybirdState>>) -> bool {
+    !matches!(
+        state.get(),
+        FlappybirdState::Dying | FlappybirdState::GameOver | FlappybirdState::Paused
+    )
+}
+
+pub fn advance_scroll(
+    time: Res<Time>,
+    speed: Res<WorldSpeed>,
+    state: Res<State<FlappybirdState>>,
+    mut scroll: ResMut<WorldScroll>,
+) {
+    scroll.delta = if world_scrolling(state) {
+        speed.0 * time.delta_seconds()
+    } else {
+        0.
+    };
+    scroll.distance += scroll.delta;
+}
+
+// Every random decision about the course comes from here so a seed replays the same run
+#[derive(Resource, Deref, DerefMut)]
+pub struct WorldRng {
+    pub seed: u64,
+    #[deref]
+    rng: ChaCha8Rng,
+}
+
+impl WorldRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Default for WorldRng {
+    fn default() -> Self {
+        Self::new(rand::thread_rng().gen())
+    }
+}
+
+// Replays and daily runs bring their own course, otherwise a fixed seed from the
+// command line or settings is reused for every run
+pub fn seed_world_rng(
+    mut commands: Commands,
+    args: Res<Args>,
+    settings: Res<Settings>,
+    mode: Res<GameMode>,
+    playback: Option<Res<ReplayPlayback>>,
+) {
+    if let Some(playback) = playback {
+        commands.insert_resource(WorldRng::new(playback.replay.seed));
+        return;
+    }
+    let seed = match *mode {
+        GameMode::Daily => daily_seed(today()),
+        // levels don't draw from the course at all
+        GameMode::Endless | GameMode::Versus | GameMode::Level => args
+            .seed
+            .or(settings.seed)
+            .unwrap_or_else(|| rand::thread_rng().gen()),
+    };
+    println!("Run seed: {}", seed);
+    commands.insert_resource(WorldRng::new(seed));
+}
@@ -22,151 +22,252 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
 
-use bevy::{prelude::*, window::PrimaryWindow};
-use bevy_kira_audio::{Audio, AudioControl};
+use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
-use rand::Rng;
 
-use crate::{player::Player, ui::score::ScoreUI, world::WorldSpeed, FlappybirdState};
+use crate::{
+    modes::GameMode,
+    player::{Eliminated, Player, Seat},
+    sfx::{PlaySfx, Sfx},
+    timestep::{GameplaySet, RenderInterpolation},
+    ui::score::ScoreUI,
+    world::{
+        course::{next_gap, GAP_STEP},
+        difficulty::Difficulty,
+    },
+    FlappybirdState, Playfield,
+};
 
-use super::SpawnTimer;
+use super::{advance_scroll, seed_world_rng, PipeSpawner, WorldRng, WorldScroll};
+
+// Pixels scrolled from one pipe to the next, 1.5 s apart at the starting normal speed
+pub const PIPE_SPACING: f32 = 225.;
 
 #[derive(Debug, Component)]
 pub struct Pipe;
 
+// Birds that have scored this pipe, leaving its gap can be reported more than once
+#[derive(Debug, Component, Default)]
+pub struct PassedBy(Vec<Entity>);
+
 #[derive(Debug, Component)]
 pub struct PipeSegment;
 
-fn spawn_pipes(
+// The end of a level, scrolls in with the pipes and goes away with them
+#[derive(Debug, Component)]
+pub struct FinishLine;
+
+// Lets a crashed bird stop colliding with the pipes without touching the pipes themselves
+pub const PIPE_GROUP: Group = Group::GROUP_3;
+
+// Pipes are drawn at twice the size, the segments are 30px wide and the score sensor
+// between them half that
+pub const PIPE_SCALE: f32 = 2.;
+pub const PIPE_HALF_WIDTH: f32 = 30. / 2.;
+pub const SENSOR_HALF_WIDTH: f32 = 15. / 2.;
+// Pipes are dropped once they are this far past the left edge
+pub const PIPE_DESPAWN_X: f32 = -30. * 3. / 2.;
+
+pub fn spawn_pipes(
     mut commands: Commands,
-    time: Res<Time>,
-    mut timer: ResMut<SpawnTimer>,
-    window_query: Query<&Window, With<PrimaryWindow>>,
+    scroll: Res<WorldScroll>,
+    mut spawner: ResMut<PipeSpawner>,
+    playfield: Res<Playfield>,
     asset_server: Res<AssetServer>,
+    mut rng: ResMut<WorldRng>,
+    difficulty: Res<Difficulty>,
 ) {
-    timer.0.tick(time.delta());
-    if timer.0.just_finished() {
-        let mut rng = rand::thread_rng();
-        let window = window_query.get_single().unwrap();
+    spawner.travelled += scroll.delta;
+    if spawner.travelled >= PIPE_SPACING {
         println!("Spawning");
+        spawner.travelled -= PIPE_SPACING;
 
-        let mut node_collider = Transform::from_xyz(
-            window.width() + 50.,
-            (window.height() / 2.) + (rng.gen_range(-3..6) as f32 * 40.),
-            1.,
+        let gap = next_gap(
+            &mut **rng,
+            spawner.last_gap,
+            &difficulty.current,
+            difficulty.curve.fairness,
+        );
+        spawner.last_gap = gap;
+        // the spacing ran out partway through the tick, the pipe has moved on since
+        spawn_pipe(
+            &mut commands,
+            &asset_server,
+            Vec2::new(
+                playfield.width + 50. - spawner.travelled,
+                (playfield.height / 2.) + (gap as f32 * GAP_STEP),
+            ),
+            difficulty.current.gap,
         );
-        node_collider.scale = Vec3::splat(2.);
+    }
+}
+
+// A pair of pipes around a gap centred on `center`, `gap` is the opening in sprite pixels
+pub fn spawn_pipe(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    center: Vec2,
+    gap: f32,
+) -> Entity {
+    let mut node_collider = Transform::from_xyz(center.x, center.y, 1.);
+    node_collider.scale = Vec3::splat(PIPE_SCALE);
 
-        const PIPE_DISTANCE: f32 = 40.;
+    // half the opening, each pipe sits this far off the middle
+    let pipe_distance = gap / 2.;
 
-        commands
-            .spawn((
-                SpatialBundle::from(node_collider),
-                Collider::cuboid(15. / 2., PIPE_DISTANCE),
+    commands
+        .spawn((
+            SpatialBundle::from(node_collider),
+            Collider::cuboid(SENSOR_HALF_WIDTH, pipe_distance),
+            ActiveEvents::COLLISION_EVENTS,
+            Sensor,
+            Pipe,
+            PassedBy::default(),
+            RenderInterpolation::default(),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                SpriteBundle {
+                    texture: asset_server
+                        .get_handle("embedded://sprites/world/pipe-up.png")
+                        .unwrap_or(asset_server.load("embedded://sprites/world/pipe-up.png")),
+                    transform: Transform::from_xyz(0., (-512. / 2.) - pipe_distance, 0.),
+                    ..Default::default()
+                },
+                Collider::cuboid(PIPE_HALF_WIDTH, 512. / 2.),
+                CollisionGroups::new(PIPE_GROUP, Group::all()),
                 ActiveEvents::COLLISION_EVENTS,
-                Sensor,
-                Pipe,
-            ))
-            .with_children(|parent| {
-                parent.spawn((
-                    SpriteBundle {
-                        texture: asset_server
-                            .get_handle("embedded://sprites/world/pipe-up.png")
-                            .unwrap_or(asset_server.load("embedded://sprites/world/pipe-up.png")),
-                        transform: Transform::from_xyz(0., (-512. / 2.) - PIPE_DISTANCE, 0.),
-                        ..Default::default()
-                    },
-                    Collider::cuboid(30. / 2., 512. / 2.),
-                    CollisionGroups::new(Group::all(), Group::all()),
-                    ActiveEvents::COLLISION_EVENTS,
-                    PipeSegment,
-                ));
-                parent.spawn((
-                    SpriteBundle {
-                        texture: asset_server
-                            .get_handle("embedded://sprites/world/pipe-down.png")
-                            .unwrap_or(asset_server.load("embedded://sprites/world/pipe-down.png")),
-                        transform: Transform::from_xyz(0., (512. / 2.) + PIPE_DISTANCE, 0.),
-                        ..Default::default()
-                    },
-                    Collider::cuboid(30. / 2., 512. / 2.),
-                    CollisionGroups::new(Group::all(), Group::all()),
-                    ActiveEvents::COLLISION_EVENTS,
-                    PipeSegment,
-                ));
-            });
-    }
+                PipeSegment,
+            ));
+            parent.spawn((
+                SpriteBundle {
+                    texture: asset_server
+                        .get_handle("embedded://sprites/world/pipe-down.png")
+                        .unwrap_or(asset_server.load("embedded://sprites/world/pipe-down.png")),
+                    transform: Transform::from_xyz(0., (512. / 2.) + pipe_distance, 0.),
+                    ..Default::default()
+                },
+                Collider::cuboid(PIPE_HALF_WIDTH, 512. / 2.),
+                CollisionGroups::new(PIPE_GROUP, Group::all()),
+                ActiveEvents::COLLISION_EVENTS,
+                PipeSegment,
+            ));
+        })
+        .id()
 }
 
 fn move_pipes(
-    time: Res<Time>,
-    speed: Res<WorldSpeed>,
-    mut query: Query<&mut Transform, With<Pipe>>,
+    scroll: Res<WorldScroll>,
+    mut query: Query<&mut Transform, Or<(With<Pipe>, With<FinishLine>)>>,
 ) {
     for mut transform in query.iter_mut() {
-        transform.translation.x -= speed.0 * time.delta_seconds();
+        transform.translation.x -= scroll.delta;
     }
 }
 
 fn pipe_reached(
     mut collision_events: EventReader<CollisionEvent>,
-    query: Query<Entity, With<Pipe>>,
-    audio: Res<Audio>,
-    asset_server: Res<AssetServer>,
-    mut player_query: Query<&mut Player, With<Player>>,
-    mut score_query: Query<&mut Text, With<ScoreUI>>,
-    time: Res<Time>,
+    mut pipe_query: Query<&mut PassedBy, With<Pipe>>,
+    mut sfx: EventWriter<PlaySfx>,
+    mut player_query: Query<(&mut Player, &Seat), Without<Eliminated>>,
+    mut score_query: Query<(&mut Text, &Seat), With<ScoreUI>>,
 ) {
-    const SCORE_COOLDOWN: f32 = 1.0; // Cooldown time in seconds
-
     for event in collision_events.read().into_iter() {
         match event {
             CollisionEvent::Started(_, _, _) => {}
             CollisionEvent::Stopped(entity1, entity2, _) => {
-                if query.get(*entity1).is_ok() || query.get(*entity2).is_ok() {
-                    if let Ok(mut player) = player_query.get_single_mut() {
-                        let current_time = time.elapsed_seconds() as f32;
-
-                        if current_time - player.last_score_time >= SCORE_COOLDOWN {
-                            player.score += 1;
-                            player.last_score_time = current_time; // Update the last score time
-
-                            for mut text in score_query.iter_mut() {
-                                text.sections[0].value = player.score.to_string();
-                            }
-
-                            audio.play(
-                                asset_server
-                                    .get_handle("embedded://audio/sfx_point.ogg")
-                                    .unwrap_or_else(|| {
-                                        asset_server.load("embedded://audio/sfx_point.ogg")
-                                    }),
-                            );
-                        }
-                    }
+                let (pipe, bird) = if pipe_query.contains(*entity1) {
+                    (*entity1, *entity2)
+                } else if pipe_query.contains(*entity2) {
+                    (*entity2, *entity1)
+                } else {
+                    continue;
+                };
+                let Ok((mut player, seat)) = player_query.get_mut(bird) else {
+                    continue;
+                };
+                let Ok(mut passed_by) = pipe_query.get_mut(pipe) else {
+                    continue;
+                };
+                // each pipe scores once, however close the next one is
+                if passed_by.0.contains(&bird) {
+                    continue;
+                }
+                passed_by.0.push(bird);
+                player.score += 1;
+
+                for (mut text, _) in score_query
+                    .iter_mut()
+                    .filter(|(_, score_seat)| *score_seat == seat)
+                {
+                    text.sections[0].value = player.score.to_string();
                 }
+
+                sfx.send(PlaySfx(Sfx::Point));
             }
         }
     }
 }
 
-fn despawn_pipes(mut commands: Commands, query: Query<(Entity, &Transform), With<Pipe>>) {
-    let effective_pipe_width = 30. * 3.;
+fn despawn_pipes(
+    mut commands: Commands,
+    query: Query<(Entity, &Transform), Or<(With<Pipe>, With<FinishLine>)>>,
+) {
     for (entity, pipe) in query.iter() {
-        if pipe.translation.x <= -effective_pipe_width / 2. {
+        if pipe.translation.x <= PIPE_DESPAWN_X {
             commands.entity(entity).despawn_recursive();
         }
     }
 }
 
+pub fn clear_pipes(
+    mut commands: Commands,
+    pipe_query: Query<Entity, Or<(With<Pipe>, With<FinishLine>)>>,
+    mut spawner: ResMut<PipeSpawner>,
+) {
+    for entity in pipe_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    *spawner = PipeSpawner::default();
+}
+
 pub struct PipePlugin;
 
 impl Plugin for PipePlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(SpawnTimer(Timer::from_seconds(1.5, TimerMode::Repeating)))
+        app.init_resource::<PipeSpawner>()
+            .init_resource::<WorldRng>()
+            .add_systems(OnEnter(FlappybirdState::TapTap), seed_world_rng)
+            .add_systems(OnExit(FlappybirdState::GameOver), clear_pipes)
+            // a run restarted from outside skips the game over screen
+            .add_systems(
+                OnTransition {
+                    exited: FlappybirdState::InGame,
+                    entered: FlappybirdState::TapTap,
+                },
+                clear_pipes,
+            )
+            .add_systems(
+                FixedUpdate,
+                // a new pipe has already moved, so it skips this tick's move
+                // levels lay out their own pipes
+                (
+                    move_pipes,
+                    spawn_pipes.run_if(not(resource_equals(GameMode::Level))),
+                    despawn_pipes,
+                )
+                    .chain()
+                    .in_set(GameplaySet::Simulation)
+                    .after(advance_scroll)
+                    .run_if(
+                        in_state(FlappybirdState::InGame)
+                            .or_else(in_state(FlappybirdState::Evolve)),
+                    ),
+            )
             .add_systems(
-                Update,
-                (spawn_pipes, move_pipes, pipe_reached, despawn_pipes)
+                FixedUpdate,
+                pipe_reached
+                    .in_set(GameplaySet::Collisions)
                     .run_if(in_state(FlappybirdState::InGame)),
             );
     }
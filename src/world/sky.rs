@@ -22,25 +22,82 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
 
-use bevy::{prelude::*, window::PrimaryWindow};
+use bevy::prelude::*;
 
-use crate::FlappybirdState;
+use crate::{
+    timestep::{GameplaySet, RenderInterpolation},
+    Playfield,
+};
 
-use super::WorldSpeed;
+use super::{
+    advance_scroll,
+    theme::{SkyTheme, ThemeFade, ThemePlugin},
+    world_scrolling, WorldScroll,
+};
 
 #[derive(Component)]
 pub struct Sky;
 
+// The incoming theme drawn on top of a sky tile while the themes cross-fade
+#[derive(Component)]
+struct SkyOverlay;
+
+// A sky tile that already carries its SkyOverlay
+#[derive(Component)]
+struct Crossfading;
+
 #[derive(Resource)]
 struct SkyOffset(pub f32);
 
+// Both skies cover the same width once scaled
+const SKY_WIDTH: f32 = 144. * 3.;
+
+fn spawn_sky_tile(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    translation: Vec3,
+    theme: SkyTheme,
+) {
+    let (texture_path, texture_scale) = theme.texture();
+    commands.spawn((
+        SpriteBundle {
+            texture: asset_server.load(texture_path),
+            transform: Transform {
+                translation,
+                scale: texture_scale,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Sky,
+        RenderInterpolation::default(),
+    ));
+}
+
+fn spawn_sky(
+    mut commands: Commands,
+    playfield: Res<Playfield>,
+    asset_server: Res<AssetServer>,
+    theme: Res<SkyTheme>,
+) {
+    let number_of_skys = (playfield.width / SKY_WIDTH).ceil() as u32 + 1;
+
+    for i in 0..number_of_skys {
+        spawn_sky_tile(
+            &mut commands,
+            &asset_server,
+            Vec3::new(i as f32 * SKY_WIDTH, playfield.height / 2., 0.),
+            *theme,
+        );
+    }
+}
+
 fn move_sky(
-    time: Res<Time>,
-    speed: Res<WorldSpeed>,
+    scroll: Res<WorldScroll>,
     mut sky_query: Query<&mut Transform, With<Sky>>,
     mut offset: ResMut<SkyOffset>,
 ) {
-    let delta_x = (speed.0 / 3.) * time.delta_seconds();
+    let delta_x = scroll.delta / 3.;
     for mut transform in sky_query.iter_mut() {
         transform.translation.x -= delta_x;
     }
@@ -51,58 +108,124 @@ fn move_sky(
 fn despawn_and_spawn_sky(
     mut commands: Commands,
     mut query: Query<(Entity, &Transform), With<Sky>>,
-    window_query: Query<&Window, With<PrimaryWindow>>,
+    playfield: Res<Playfield>,
     asset_server: Res<AssetServer>,
+    theme: Res<SkyTheme>,
     mut offset: ResMut<SkyOffset>,
 ) {
-    let window = window_query.get_single().unwrap();
-    let texture_handle = asset_server
-        .get_handle("embedded://sprites/world/day-sky.png")
-        .unwrap();
-    let texture_width = 144.0; // Adjust based on your texture width
-    let texture_scale = Vec3::splat(3.); // Adjust based on your texture scale
-
-    let effective_width = texture_width * texture_scale.x;
-
     let mut sky_entities: Vec<(Entity, &Transform)> = query.iter_mut().collect();
     sky_entities.sort_by(|a, b| a.1.translation.x.partial_cmp(&b.1.translation.x).unwrap());
 
     // Despawn entities that have moved out of the left boundary
     for (entity, transform) in sky_entities.iter() {
-        if transform.translation.x <= -effective_width / 2. {
+        if transform.translation.x <= -SKY_WIDTH / 2. {
             println!("Despawning entity");
-            commands.entity(*entity).despawn();
+            commands.entity(*entity).despawn_recursive();
             // Spawn new ground entities on the right if needed
             let rightmost_x = sky_entities
                 .last()
-                .map_or(-window.width() / 2., |(_, transform)| {
+                .map_or(-playfield.width / 2., |(_, transform)| {
                     transform.translation.x
                 });
-            let new_x = rightmost_x + effective_width - offset.0;
-            commands.spawn((
-                SpriteBundle {
-                    texture: texture_handle.clone(),
-                    transform: Transform {
-                        translation: Vec3::new(new_x, window.height() / 2., 0.),
-                        scale: texture_scale,
-                        ..Default::default()
-                    },
-                    ..Default::default()
-                },
-                Sky,
-            ));
+            let new_x = rightmost_x + SKY_WIDTH - offset.0;
+            spawn_sky_tile(
+                &mut commands,
+                &asset_server,
+                Vec3::new(new_x, playfield.height / 2., 0.),
+                *theme,
+            );
         }
     }
 
     offset.0 = 0.;
 }
 
+// Swaps every tile over to the new theme and drops the cross-fade overlays
+fn apply_sky_theme(
+    mut commands: Commands,
+    theme: Res<SkyTheme>,
+    asset_server: Res<AssetServer>,
+    mut query: Query<(Entity, &mut Handle<Image>, &mut Transform), With<Sky>>,
+    overlay_query: Query<Entity, With<SkyOverlay>>,
+) {
+    let (texture_path, texture_scale) = theme.texture();
+    let texture_handle = asset_server.load(texture_path);
+    for (entity, mut texture, mut transform) in query.iter_mut() {
+        *texture = texture_handle.clone();
+        transform.scale = texture_scale;
+        commands.entity(entity).remove::<Crossfading>();
+    }
+    for entity in overlay_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn crossfade_sky(
+    mut commands: Commands,
+    fade: Res<ThemeFade>,
+    asset_server: Res<AssetServer>,
+    tile_query: Query<(Entity, &Transform), (With<Sky>, Without<Crossfading>)>,
+    mut overlay_query: Query<&mut Sprite, With<SkyOverlay>>,
+) {
+    // the theme swaps over this frame, a new overlay would outlive the fade
+    if fade.timer.finished() {
+        return;
+    }
+    let alpha = fade.timer.fraction();
+    let (texture_path, texture_scale) = fade.to.texture();
+    for (entity, transform) in tile_query.iter() {
+        commands
+            .entity(entity)
+            .insert(Crossfading)
+            .with_children(|parent| {
+                parent.spawn((
+                    SpriteBundle {
+                        texture: asset_server.load(texture_path),
+                        sprite: Sprite {
+                            color: Color::srgba(1., 1., 1., alpha),
+                            ..default()
+                        },
+                        // undo the tile's own scale, the two skies differ in resolution
+                        transform: Transform {
+                            translation: Vec3::new(0., 0., 0.01),
+                            scale: texture_scale / transform.scale,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    SkyOverlay,
+                ));
+            });
+    }
+    for mut sprite in overlay_query.iter_mut() {
+        sprite.color.set_alpha(alpha);
+    }
+}
+
 pub struct SkyPlugin;
 impl Plugin for SkyPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(SkyOffset(0.)).add_systems(
-            Update,
-            (move_sky, despawn_and_spawn_sky).run_if(not(in_state(FlappybirdState::GameOver))),
-        );
+        app.add_plugins(ThemePlugin)
+            .insert_resource(SkyOffset(0.))
+            .add_systems(Startup, spawn_sky)
+            .add_systems(
+                FixedUpdate,
+                (move_sky, despawn_and_spawn_sky)
+                    .chain()
+                    .in_set(GameplaySet::Simulation)
+                    .after(advance_scroll)
+                    .run_if(world_scrolling),
+            )
+            .add_systems(
+                Update,
+                (
+                    // a fade cut short by a new run leaves overlays behind too
+                    apply_sky_theme.run_if(
+                        resource_changed::<SkyTheme>.or_else(resource_removed::<ThemeFade>),
+                    ),
+                    crossfade_sky.run_if(resource_exists::<ThemeFade>),
+                )
+                    .chain(),
+            );
     }
 }
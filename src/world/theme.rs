@@ -0,0 +1,210 @@
+/*
+MIT License
+
+Copyright (c) 2024 freehelpdesk
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use bevy::prelude::*;
+use chrono::Timelike;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{ui::settings::Settings, FlappybirdState};
+
+const THEME_CYCLE: f32 = 60.; // seconds of flying before the sky turns
+const THEME_FADE: f32 = 5.;
+// the local hours that count as day for ThemePreference::Clock
+const DAYTIME: std::ops::Range<u32> = 7..19;
+
+// How the sky for a run is chosen, stored in the settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ThemePreference {
+    #[default]
+    Day,
+    Night,
+    Random,
+    Clock,
+}
+
+impl ThemePreference {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ThemePreference::Day => "DAY",
+            ThemePreference::Night => "NIGHT",
+            ThemePreference::Random => "RANDOM",
+            ThemePreference::Clock => "CLOCK",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            ThemePreference::Day => ThemePreference::Night,
+            ThemePreference::Night => ThemePreference::Random,
+            ThemePreference::Random => ThemePreference::Clock,
+            ThemePreference::Clock => ThemePreference::Day,
+        }
+    }
+
+    // Random uses its own rng, the world rng is kept for the course
+    pub fn pick(&self) -> SkyTheme {
+        match self {
+            ThemePreference::Day => SkyTheme::Day,
+            ThemePreference::Night => SkyTheme::Night,
+            ThemePreference::Random => {
+                if rand::thread_rng().gen() {
+                    SkyTheme::Day
+                } else {
+                    SkyTheme::Night
+                }
+            }
+            ThemePreference::Clock => {
+                if DAYTIME.contains(&chrono::Local::now().hour()) {
+                    SkyTheme::Day
+                } else {
+                    SkyTheme::Night
+                }
+            }
+        }
+    }
+}
+
+// The sky currently on screen
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SkyTheme {
+    #[default]
+    Day,
+    Night,
+}
+
+impl SkyTheme {
+    // The night sky is drawn at twice the resolution, so it gets half the scale
+    pub fn texture(&self) -> (&'static str, Vec3) {
+        match self {
+            SkyTheme::Day => ("embedded://sprites/world/day-sky.png", Vec3::splat(3.)),
+            SkyTheme::Night => ("embedded://sprites/world/night-sky.png", Vec3::splat(1.5)),
+        }
+    }
+
+    pub fn other(&self) -> Self {
+        match self {
+            SkyTheme::Day => SkyTheme::Night,
+            SkyTheme::Night => SkyTheme::Day,
+        }
+    }
+}
+
+// Present while the sky is blending over to `to`, SkyTheme switches once it is done
+#[derive(Resource, Debug)]
+pub struct ThemeFade {
+    pub to: SkyTheme,
+    pub timer: Timer,
+}
+
+#[derive(Resource)]
+struct ThemeCycle(Timer);
+
+#[derive(Resource, Default)]
+struct AppliedPreference(Option<ThemePreference>);
+
+// Only a changed preference re-rolls the sky, not every other settings toggle
+fn apply_theme_preference(
+    settings: Res<Settings>,
+    mut applied: ResMut<AppliedPreference>,
+    mut theme: ResMut<SkyTheme>,
+) {
+    if applied.0 == Some(settings.theme) {
+        return;
+    }
+    applied.0 = Some(settings.theme);
+    theme.set_if_neq(settings.theme.pick());
+}
+
+fn pick_run_theme(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut theme: ResMut<SkyTheme>,
+    mut cycle: ResMut<ThemeCycle>,
+) {
+    commands.remove_resource::<ThemeFade>();
+    cycle.0.reset();
+    theme.set_if_neq(settings.theme.pick());
+}
+
+fn cycle_theme(
+    mut commands: Commands,
+    time: Res<Time>,
+    theme: Res<SkyTheme>,
+    mut cycle: ResMut<ThemeCycle>,
+) {
+    if cycle.0.tick(time.delta()).just_finished() {
+        println!("The sky turns to {:?}", theme.other());
+        commands.insert_resource(ThemeFade {
+            to: theme.other(),
+            timer: Timer::from_seconds(THEME_FADE, TimerMode::Once),
+        });
+    }
+}
+
+fn advance_fade(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut fade: ResMut<ThemeFade>,
+    mut theme: ResMut<SkyTheme>,
+) {
+    if fade.timer.tick(time.delta()).finished() {
+        *theme = fade.to;
+        commands.remove_resource::<ThemeFade>();
+    }
+}
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SkyTheme>()
+            .init_resource::<AppliedPreference>()
+            .insert_resource(ThemeCycle(Timer::from_seconds(
+                THEME_CYCLE,
+                TimerMode::Repeating,
+            )))
+            .add_systems(PreStartup, apply_theme_preference)
+            .add_systems(
+                Update,
+                apply_theme_preference.run_if(resource_changed::<Settings>),
+            )
+            .add_systems(OnEnter(FlappybirdState::TapTap), pick_run_theme)
+            .add_systems(
+                Update,
+                (
+                    cycle_theme.run_if(
+                        in_state(FlappybirdState::InGame)
+                            .and_then(|settings: Res<Settings>| settings.theme_cycle)
+                            .and_then(not(resource_exists::<ThemeFade>)),
+                    ),
+                    advance_fade.run_if(
+                        resource_exists::<ThemeFade>
+                            .and_then(not(in_state(FlappybirdState::Paused))),
+                    ),
+                )
+                    .chain(),
+            );
+    }
+}